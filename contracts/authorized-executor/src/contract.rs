@@ -0,0 +1,206 @@
+use std::marker::PhantomData;
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, wasm_execute, Addr, Binary, CosmosMsg, CustomMsg, Deps, DepsMut, Empty, Env,
+    MessageInfo, Response, StdResult,
+};
+use cw2::set_contract_version;
+use cw_authorizations::migration::assert_can_migrate;
+use cw_authorizations::msg::{
+    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, AuthorizationContext, IsAuthorizedResponse,
+};
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{Config, ExecutorState};
+
+const CONTRACT_NAME: &str = "crates.io:authorized-executor";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// `C` is the custom message type of the chain. The executed messages, and the
+/// ones sent to the authorization, are `CosmosMsg<C>`. The entry points of this
+/// crate use `Empty`; chains with custom messages can call this from their own
+/// entry points.
+pub struct AuthorizedExecutor<C = Empty> {
+    state: ExecutorState,
+    custom: PhantomData<C>,
+}
+
+impl<C> AuthorizedExecutor<C> {
+    pub const fn new() -> Self {
+        AuthorizedExecutor {
+            state: ExecutorState::new(),
+            custom: PhantomData,
+        }
+    }
+}
+
+impl<C> Default for AuthorizedExecutor<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: CustomMsg> AuthorizedExecutor<C> {
+    pub fn instantiate(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> Result<Response<C>, ContractError> {
+        set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        let config = Config {
+            authorization: deps.api.addr_validate(msg.authorization.as_str())?,
+        };
+        self.state.config.save(deps.storage, &config)?;
+        let owner = deps.api.addr_validate(msg.owner.as_str())?;
+        self.state.ownership.initialize(deps.storage, owner)?;
+        Ok(Response::default().add_attribute("action", "instantiate"))
+    }
+
+    pub fn execute(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: ExecuteMsg<C>,
+    ) -> Result<Response<C>, ContractError> {
+        match msg {
+            ExecuteMsg::Execute { msgs } => self.execute_execute(deps, info, msgs),
+            ExecuteMsg::UpdateAuthorization { addr } => {
+                self.execute_update_authorization(deps, info, addr)
+            }
+            ExecuteMsg::UpdateOwnership(action) => {
+                let ownership =
+                    self.state
+                        .ownership
+                        .update(deps.api, deps.storage, &info.sender, action)?;
+                Ok(Response::default()
+                    .add_attribute("action", "update_ownership")
+                    .add_attribute(
+                        "owner",
+                        ownership
+                            .owner
+                            .map(String::from)
+                            .unwrap_or_else(|| "none".to_string()),
+                    ))
+            }
+        }
+    }
+
+    fn execute_execute(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        msgs: Vec<CosmosMsg<C>>,
+    ) -> Result<Response<C>, ContractError> {
+        let config = self.state.config.load(deps.storage)?;
+        let context = AuthorizationContext {
+            funds: info.funds,
+            ..AuthorizationContext::new(info.sender)
+        };
+
+        let response: IsAuthorizedResponse = deps.querier.query_wasm_smart(
+            config.authorization.clone(),
+            &AuthoriazationQueryMsg::<Empty, C>::IsAuthorized {
+                msgs: msgs.clone(),
+                context: context.clone(),
+            },
+        )?;
+        if !response.authorized {
+            return Err(ContractError::ExecutionNotAuthorized {
+                code: response.code,
+                reason: response.reason,
+            });
+        }
+
+        // The update is sent as a regular message after the executed ones, so it
+        // only happens if all of them succeed and a failing update reverts them.
+        let update = wasm_execute(
+            config.authorization,
+            &AuthoriazationExecuteMsg::<Empty, C>::UpdateExecutedAuthorizationState {
+                msgs: msgs.clone(),
+                context,
+            },
+            vec![],
+        )?;
+
+        Ok(Response::default()
+            .add_attribute("action", "execute")
+            .add_messages(msgs)
+            .add_message(update))
+    }
+
+    fn execute_update_authorization(
+        &self,
+        deps: DepsMut,
+        info: MessageInfo,
+        addr: Addr,
+    ) -> Result<Response<C>, ContractError> {
+        self.state
+            .ownership
+            .assert_owner(deps.storage, &info.sender)?;
+        let mut config = self.state.config.load(deps.storage)?;
+        config.authorization = deps.api.addr_validate(addr.as_str())?;
+        self.state.config.save(deps.storage, &config)?;
+        Ok(Response::default()
+            .add_attribute("action", "update_authorization")
+            .add_attribute("authorization", config.authorization))
+    }
+
+    pub fn migrate(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        _msg: MigrateMsg,
+    ) -> Result<Response<C>, ContractError> {
+        let from_version = assert_can_migrate(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        Ok(Response::default()
+            .add_attribute("action", "migrate")
+            .add_attribute("from_version", from_version.to_string())
+            .add_attribute("to_version", CONTRACT_VERSION))
+    }
+
+    pub fn query(&self, deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+        match msg {
+            QueryMsg::Config {} => to_binary(&self.state.config.load(deps.storage)?),
+            QueryMsg::Ownership {} => {
+                to_binary(&self.state.ownership.ownership.load(deps.storage)?)
+            }
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    AuthorizedExecutor::<Empty>::new().instantiate(deps, env, info, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    AuthorizedExecutor::<Empty>::new().execute(deps, env, info, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    AuthorizedExecutor::<Empty>::new().migrate(deps, env, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    AuthorizedExecutor::<Empty>::new().query(deps, env, msg)
+}
@@ -0,0 +1,18 @@
+use cosmwasm_std::StdError;
+use cw_authorizations::AuthorizationError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Authorization(#[from] AuthorizationError),
+
+    #[error("Not authorized to execute the messages ({code:?}): {reason:?}")]
+    ExecutionNotAuthorized {
+        code: Option<String>,
+        reason: Option<String>,
+    },
+}
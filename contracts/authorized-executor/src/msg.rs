@@ -0,0 +1,38 @@
+use cosmwasm_std::{Addr, CosmosMsg, Empty};
+use cw_authorizations::ownership::OwnershipAction;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct InstantiateMsg {
+    /// The address that can change which authorization this contract uses
+    pub owner: Addr,
+    /// The root of the authorization tree that decides who can execute
+    /// messages through this contract
+    pub authorization: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg<C = Empty> {
+    /// Executes the messages on behalf of this contract if the authorization
+    /// allows the sender to do so.
+    Execute { msgs: Vec<CosmosMsg<C>> },
+    /// Points the executor at a different authorization contract. Only the
+    /// owner can do this.
+    UpdateAuthorization { addr: Addr },
+    /// Proposes, accepts or renounces the ownership of the contract
+    UpdateOwnership(OwnershipAction),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    Ownership {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MigrateMsg {}
@@ -0,0 +1,32 @@
+use cosmwasm_std::Addr;
+use cw_authorizations::ownership::OwnershipState;
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+pub struct Config {
+    /// The root of the authorization tree. It is queried before executing
+    /// any messages and notified after they have been executed.
+    pub authorization: Addr,
+}
+
+pub struct ExecutorState {
+    pub config: Item<'static, Config>,
+    pub ownership: OwnershipState,
+}
+
+impl ExecutorState {
+    pub const fn new() -> Self {
+        ExecutorState {
+            config: Item::new("config"),
+            ownership: OwnershipState::new(),
+        }
+    }
+}
+
+impl Default for ExecutorState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
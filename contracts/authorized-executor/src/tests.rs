@@ -0,0 +1,431 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{
+    coin, coins, from_binary, to_binary, Addr, BankMsg, ContractResult, CosmosMsg, CustomMsg,
+    Empty, StakingMsg, SystemError, SystemResult, Uint128, WasmMsg, WasmQuery,
+};
+use cw_authorizations::msg::{AuthoriazationExecuteMsg, IsAuthorizedResponse};
+use cw_authorizations::ownership::{Ownership, OwnershipAction};
+use cw_authorizations::AuthorizationError;
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::AuthorizedExecutor;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::Config;
+use crate::ContractError;
+
+const CREATOR: &str = "creator";
+const MEMBER: &str = "member";
+const RECIPIENT: &str = "recipient";
+
+fn executor() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn message_filter() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        message_filter::contract::execute,
+        message_filter::contract::instantiate,
+        message_filter::contract::query,
+    );
+    Box::new(contract)
+}
+
+/// Creates a funded executor whose authorization only allows MEMBER to send
+/// bank messages.
+fn setup(app: &mut App) -> (Addr, Addr) {
+    let creator = Addr::unchecked(CREATOR);
+
+    let filter_id = app.store_code(message_filter());
+    let filter_addr = app
+        .instantiate_contract(
+            filter_id,
+            creator.clone(),
+            &message_filter::msg::InstantiateMsg {
+                owner: creator.clone(),
+                kind: message_filter::state::Kind::Allow {},
+                decode_binaries: false,
+                conflict_strategy: Default::default(),
+                evaluation_mode: Default::default(),
+            },
+            &[],
+            "Message Filter",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        creator.clone(),
+        filter_addr.clone(),
+        &AuthoriazationExecuteMsg::<message_filter::msg::ExecuteMsg>::Extension(
+            message_filter::msg::ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked(MEMBER),
+                msg: r#"{"bank": {}}"#.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: None,
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+
+    let executor_id = app.store_code(executor());
+    let executor_addr = app
+        .instantiate_contract(
+            executor_id,
+            creator.clone(),
+            &InstantiateMsg {
+                owner: creator,
+                authorization: filter_addr.clone(),
+            },
+            &coins(1000, "ujuno"),
+            "Authorized Executor",
+            None,
+        )
+        .unwrap();
+
+    (executor_addr, filter_addr)
+}
+
+#[test]
+fn test_execute_authorized() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(CREATOR), coins(1000, "ujuno"))
+            .unwrap();
+    });
+    let (executor_addr, _) = setup(&mut app);
+
+    let send: CosmosMsg = BankMsg::Send {
+        to_address: RECIPIENT.to_string(),
+        amount: coins(100, "ujuno"),
+    }
+    .into();
+    app.execute_contract(
+        Addr::unchecked(MEMBER),
+        executor_addr.clone(),
+        &ExecuteMsg::Execute { msgs: vec![send] },
+        &[],
+    )
+    .unwrap();
+
+    let balance = app.wrap().query_balance(RECIPIENT, "ujuno").unwrap();
+    assert_eq!(balance, coin(100, "ujuno"));
+    let balance = app.wrap().query_balance(executor_addr, "ujuno").unwrap();
+    assert_eq!(balance, coin(900, "ujuno"));
+}
+
+#[test]
+fn test_execute_unauthorized() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(CREATOR), coins(1000, "ujuno"))
+            .unwrap();
+    });
+    let (executor_addr, _) = setup(&mut app);
+
+    // Not allowed to execute anything
+    let send: CosmosMsg = BankMsg::Send {
+        to_address: RECIPIENT.to_string(),
+        amount: coins(100, "ujuno"),
+    }
+    .into();
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked("someone_else"),
+            executor_addr.clone(),
+            &ExecuteMsg::Execute { msgs: vec![send] },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert!(matches!(
+        err,
+        ContractError::ExecutionNotAuthorized { code: Some(code), .. } if code == "no_matching_authorization"
+    ));
+
+    // Not allowed to execute these messages
+    let delegate: CosmosMsg = StakingMsg::Delegate {
+        validator: "validator".to_string(),
+        amount: coin(100, "ujuno"),
+    }
+    .into();
+    app.execute_contract(
+        Addr::unchecked(MEMBER),
+        executor_addr.clone(),
+        &ExecuteMsg::Execute {
+            msgs: vec![delegate],
+        },
+        &[],
+    )
+    .unwrap_err();
+
+    let balance = app.wrap().query_balance(executor_addr, "ujuno").unwrap();
+    assert_eq!(balance, coin(1000, "ujuno"));
+}
+
+#[test]
+fn test_update_authorization() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(CREATOR), coins(1000, "ujuno"))
+            .unwrap();
+    });
+    let (executor_addr, filter_addr) = setup(&mut app);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(MEMBER),
+            executor_addr.clone(),
+            &ExecuteMsg::<Empty>::UpdateAuthorization {
+                addr: Addr::unchecked("other"),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert!(matches!(
+        err,
+        ContractError::Authorization(AuthorizationError::Unauthorized { .. })
+    ));
+
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        executor_addr.clone(),
+        &ExecuteMsg::<Empty>::UpdateAuthorization {
+            addr: Addr::unchecked("other"),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let config: Config = app
+        .wrap()
+        .query_wasm_smart(executor_addr, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.authorization, Addr::unchecked("other"));
+    assert_ne!(config.authorization, filter_addr);
+}
+
+#[test]
+fn test_ownership_transfer() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(CREATOR), coins(1000, "ujuno"))
+            .unwrap();
+    });
+    let (executor_addr, _) = setup(&mut app);
+
+    let update_ownership = |app: &mut App, sender: &str, action: OwnershipAction| {
+        app.execute_contract(
+            Addr::unchecked(sender),
+            executor_addr.clone(),
+            &ExecuteMsg::<Empty>::UpdateOwnership(action),
+            &[],
+        )
+    };
+
+    // Only the owner can propose a new owner
+    update_ownership(
+        &mut app,
+        MEMBER,
+        OwnershipAction::ProposeOwner {
+            new_owner: MEMBER.to_string(),
+        },
+    )
+    .unwrap_err();
+    update_ownership(
+        &mut app,
+        CREATOR,
+        OwnershipAction::ProposeOwner {
+            new_owner: MEMBER.to_string(),
+        },
+    )
+    .unwrap();
+
+    // The proposal doesn't change the owner until it is accepted, and only
+    // the proposed owner can accept it
+    let ownership: Ownership = app
+        .wrap()
+        .query_wasm_smart(executor_addr.clone(), &QueryMsg::Ownership {})
+        .unwrap();
+    assert_eq!(ownership.owner, Some(Addr::unchecked(CREATOR)));
+    assert_eq!(ownership.pending_owner, Some(Addr::unchecked(MEMBER)));
+    update_ownership(&mut app, RECIPIENT, OwnershipAction::AcceptOwnership {}).unwrap_err();
+    update_ownership(&mut app, MEMBER, OwnershipAction::AcceptOwnership {}).unwrap();
+
+    let ownership: Ownership = app
+        .wrap()
+        .query_wasm_smart(executor_addr.clone(), &QueryMsg::Ownership {})
+        .unwrap();
+    assert_eq!(ownership.owner, Some(Addr::unchecked(MEMBER)));
+    assert_eq!(ownership.pending_owner, None);
+
+    // The previous owner lost its permissions
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        executor_addr.clone(),
+        &ExecuteMsg::<Empty>::UpdateAuthorization {
+            addr: Addr::unchecked("other"),
+        },
+        &[],
+    )
+    .unwrap_err();
+
+    // Nobody can administer the contract after renouncing
+    update_ownership(&mut app, MEMBER, OwnershipAction::RenounceOwnership {}).unwrap();
+    app.execute_contract(
+        Addr::unchecked(MEMBER),
+        executor_addr,
+        &ExecuteMsg::<Empty>::UpdateAuthorization {
+            addr: Addr::unchecked("other"),
+        },
+        &[],
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn test_migrate() {
+    let mut app = App::default();
+    let creator = Addr::unchecked(CREATOR);
+
+    let filter_id = app.store_code(message_filter());
+    let filter_addr = app
+        .instantiate_contract(
+            filter_id,
+            creator.clone(),
+            &message_filter::msg::InstantiateMsg {
+                owner: creator.clone(),
+                kind: message_filter::state::Kind::Allow {},
+                decode_binaries: false,
+                conflict_strategy: Default::default(),
+                evaluation_mode: Default::default(),
+            },
+            &[],
+            "Message Filter",
+            Some(CREATOR.to_string()),
+        )
+        .unwrap();
+    let executor_id = app.store_code(executor());
+    let executor_addr = app
+        .instantiate_contract(
+            executor_id,
+            creator.clone(),
+            &InstantiateMsg {
+                owner: creator.clone(),
+                authorization: filter_addr.clone(),
+            },
+            &[],
+            "Authorized Executor",
+            Some(CREATOR.to_string()),
+        )
+        .unwrap();
+
+    app.migrate_contract(
+        creator.clone(),
+        executor_addr.clone(),
+        &MigrateMsg {},
+        executor_id,
+    )
+    .unwrap();
+    let config: Config = app
+        .wrap()
+        .query_wasm_smart(executor_addr, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.authorization, filter_addr);
+
+    // Other contracts can't be migrated into an executor
+    let err = app
+        .migrate_contract(creator, filter_addr, &MigrateMsg {}, executor_id)
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("Cannot migrate from crates.io:message-filter"));
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum TokenFactoryMsg {
+    Mint { denom: String, amount: Uint128 },
+}
+
+impl CustomMsg for TokenFactoryMsg {}
+
+#[test]
+fn test_execute_custom_messages() {
+    let mut deps = mock_dependencies();
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { contract_addr, .. } if contract_addr == "authorization" => {
+            SystemResult::Ok(ContractResult::Ok(
+                to_binary(&IsAuthorizedResponse::authorized()).unwrap(),
+            ))
+        }
+        _ => SystemResult::Err(SystemError::UnsupportedRequest {
+            kind: "unknown contract".to_string(),
+        }),
+    });
+    let executor = AuthorizedExecutor::<TokenFactoryMsg>::new();
+    executor
+        .instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(CREATOR, &[]),
+            InstantiateMsg {
+                owner: Addr::unchecked(CREATOR),
+                authorization: Addr::unchecked("authorization"),
+            },
+        )
+        .unwrap();
+
+    let mint = CosmosMsg::Custom(TokenFactoryMsg::Mint {
+        denom: "factory/ujuno".to_string(),
+        amount: Uint128::new(100),
+    });
+    let response = executor
+        .execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(MEMBER, &[]),
+            ExecuteMsg::Execute {
+                msgs: vec![mint.clone()],
+            },
+        )
+        .unwrap();
+
+    assert_eq!(response.messages.len(), 2);
+    assert_eq!(response.messages[0].msg, mint);
+    // The authorization is told about the custom message too
+    match &response.messages[1].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) => {
+            assert_eq!(contract_addr, "authorization");
+            let update: AuthoriazationExecuteMsg<Empty, TokenFactoryMsg> =
+                from_binary(msg).unwrap();
+            assert_eq!(
+                update,
+                AuthoriazationExecuteMsg::UpdateExecutedAuthorizationState {
+                    msgs: vec![mint],
+                    context: Addr::unchecked(MEMBER).into(),
+                }
+            );
+        }
+        msg => panic!("unexpected message {:?}", msg),
+    }
+}
@@ -1,24 +1,38 @@
-#[cfg(not(feature = "library"))]
-use cosmwasm_std::entry_point;
+use cosmwasm_std::{Addr, CosmosMsg, Deps, DepsMut, Env};
 
-use cosmwasm_std::{Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-
-use cw_authorizations::msg::{AuthoriazationExecuteMsg, AuthoriazationQueryMsg};
+use cw_authorizations::msg::AuthorizationContext;
 use cw_authorizations::{Authorization, AuthorizationError};
+use cw_authorizations_derive::authorization_contract;
 use serde::{Deserialize, Serialize};
 
 pub struct BasicAuth {}
 
+impl BasicAuth {
+    pub fn instantiate(
+        &self,
+        _deps: DepsMut,
+        _msg: InstantiateMsg,
+    ) -> Result<(), AuthorizationError> {
+        Ok(())
+    }
+}
+
+#[authorization_contract(instantiate = InstantiateMsg)]
 impl Authorization for BasicAuth {
     fn new() -> Self {
         BasicAuth {}
     }
 
+    fn contract_type(&self) -> String {
+        "basic".to_string()
+    }
+
     fn is_authorized(
         &self,
         _deps: Deps,
+        _env: &Env,
         _msgs: &Vec<CosmosMsg>,
-        _sender: &Addr,
+        _context: &AuthorizationContext,
     ) -> Result<bool, AuthorizationError> {
         Ok(true)
     }
@@ -32,30 +46,5 @@ impl Authorization for BasicAuth {
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn instantiate(
-    _deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    _msg: InstantiateMsg,
-) -> Result<Response, AuthorizationError> {
-    Ok(Response::default().add_attribute("action", "instantiate"))
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: AuthoriazationExecuteMsg,
-) -> Result<Response, AuthorizationError> {
-    BasicAuth::new().execute(deps, env, info, msg)
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: AuthoriazationQueryMsg) -> StdResult<Binary> {
-    BasicAuth::new().query(deps, env, msg)
-}
-
 #[cfg(test)]
 mod tests;
@@ -1,8 +1,12 @@
-use cosmwasm_std::{coin, Addr, Empty};
+use cosmwasm_std::{
+    coin, Addr, BankMsg, Binary, CosmosMsg, CustomMsg, Empty, QueryRequest, WasmQuery,
+};
 use cw_authorizations::msg::{
-    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, IsAuthorizedResponse,
+    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, AuthorizationContext, IsAuthorizedResponse,
 };
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 const CREATOR_ADDR: &str = "creator";
 
@@ -43,7 +47,7 @@ fn test_basic() {
         contract_addr.clone(),
         &AuthoriazationExecuteMsg::UpdateExecutedAuthorizationState::<Empty> {
             msgs: vec![],
-            sender: Addr::unchecked("Anyone"),
+            context: Addr::unchecked("Anyone").into(),
         },
         &[],
     )
@@ -55,9 +59,228 @@ fn test_basic() {
             contract_addr.clone(),
             &AuthoriazationQueryMsg::IsAuthorized::<Empty> {
                 msgs: vec![],
-                sender: Addr::unchecked("test"),
+                context: Addr::unchecked("test").into(),
             },
         )
         .unwrap();
     assert!(response.authorized)
 }
+
+/// An authorization that requires a deposit to be attached to the execution
+mod deposit {
+    use cosmwasm_std::{Addr, CosmosMsg, Deps, DepsMut, Env};
+    use cw_authorizations::msg::AuthorizationContext;
+    use cw_authorizations::{Authorization, AuthorizationError};
+    use cw_authorizations_derive::authorization_contract;
+
+    pub struct DepositAuth {}
+
+    impl DepositAuth {
+        pub fn instantiate(
+            &self,
+            _deps: DepsMut,
+            _msg: crate::InstantiateMsg,
+        ) -> Result<(), AuthorizationError> {
+            Ok(())
+        }
+    }
+
+    #[authorization_contract(instantiate = crate::InstantiateMsg)]
+    impl Authorization for DepositAuth {
+        fn new() -> Self {
+            DepositAuth {}
+        }
+
+        fn contract_type(&self) -> String {
+            "deposit".to_string()
+        }
+
+        fn is_authorized(
+            &self,
+            _deps: Deps,
+            _env: &Env,
+            _msgs: &Vec<CosmosMsg>,
+            context: &AuthorizationContext,
+        ) -> Result<bool, AuthorizationError> {
+            let deposit = context
+                .funds
+                .iter()
+                .find(|coin| coin.denom == "juno")
+                .map(|coin| coin.amount.u128())
+                .unwrap_or_default();
+            if deposit < 100 {
+                return Err(AuthorizationError::Unauthorized {
+                    reason: Some("A deposit of 100juno is required".to_string()),
+                });
+            }
+            Ok(true)
+        }
+
+        fn get_sub_authorizations(&self, _deps: Deps) -> Result<Vec<Addr>, AuthorizationError> {
+            Ok(vec![])
+        }
+    }
+}
+
+#[test]
+fn test_context_funds() {
+    let mut app = App::default();
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        deposit::execute,
+        deposit::instantiate,
+        deposit::query,
+    )));
+    let contract_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(CREATOR_ADDR),
+            &crate::InstantiateMsg {},
+            &[],
+            "Deposit Auth",
+            None,
+        )
+        .unwrap();
+    let is_authorized = |context: AuthorizationContext| -> IsAuthorizedResponse {
+        app.wrap()
+            .query_wasm_smart(
+                contract_addr.clone(),
+                &AuthoriazationQueryMsg::IsAuthorized::<Empty> {
+                    msgs: vec![],
+                    context,
+                },
+            )
+            .unwrap()
+    };
+
+    let mut context = AuthorizationContext::new(Addr::unchecked("someone"));
+    let response = is_authorized(context.clone());
+    assert!(!response.authorized);
+    assert_eq!(response.reason.unwrap(), "A deposit of 100juno is required");
+
+    context.funds = vec![coin(10, "other"), coin(100, "juno")];
+    assert!(is_authorized(context).authorized);
+
+    // Callers that don't attach funds can leave them out
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: contract_addr.to_string(),
+            msg: Binary::from(
+                br#"{"is_authorized": {"msgs": [], "context": {"sender": "someone"}}}"#.to_vec(),
+            ),
+        }))
+        .unwrap();
+    assert!(!response.authorized);
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenFactoryMsg {
+    Mint { denom: String },
+}
+
+impl CustomMsg for TokenFactoryMsg {}
+
+/// An authorization that only allows custom messages, with entry points for a
+/// chain with token factory messages
+mod custom_only {
+    use std::marker::PhantomData;
+
+    use cosmwasm_std::{Addr, CosmosMsg, CustomMsg, Deps, DepsMut, Empty, Env};
+    use cw_authorizations::error::EmptyError;
+    use cw_authorizations::msg::AuthorizationContext;
+    use cw_authorizations::{Authorization, AuthorizationError};
+    use cw_authorizations_derive::authorization_contract;
+
+    use super::TokenFactoryMsg;
+
+    pub struct CustomOnlyAuth<C = Empty> {
+        custom: PhantomData<C>,
+    }
+
+    impl<C> CustomOnlyAuth<C> {
+        pub fn instantiate(
+            &self,
+            _deps: DepsMut,
+            _msg: crate::InstantiateMsg,
+        ) -> Result<(), AuthorizationError> {
+            Ok(())
+        }
+    }
+
+    #[authorization_contract(instantiate = crate::InstantiateMsg, custom = TokenFactoryMsg)]
+    impl<C: CustomMsg> Authorization<Empty, Empty, EmptyError, C> for CustomOnlyAuth<C> {
+        fn new() -> Self {
+            CustomOnlyAuth {
+                custom: PhantomData,
+            }
+        }
+
+        fn contract_type(&self) -> String {
+            "custom_only".to_string()
+        }
+
+        fn is_authorized(
+            &self,
+            _deps: Deps,
+            _env: &Env,
+            msgs: &Vec<CosmosMsg<C>>,
+            _context: &AuthorizationContext,
+        ) -> Result<bool, AuthorizationError> {
+            if !msgs.iter().all(|msg| matches!(msg, CosmosMsg::Custom(_))) {
+                return Err(AuthorizationError::Unauthorized {
+                    reason: Some("Only custom messages are allowed".to_string()),
+                });
+            }
+            Ok(true)
+        }
+
+        fn get_sub_authorizations(&self, _deps: Deps) -> Result<Vec<Addr>, AuthorizationError> {
+            Ok(vec![])
+        }
+    }
+}
+
+#[test]
+fn test_custom_entry_points() {
+    let mut app = App::default();
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        custom_only::execute,
+        custom_only::instantiate,
+        custom_only::query,
+    )));
+    let contract_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(CREATOR_ADDR),
+            &crate::InstantiateMsg {},
+            &[],
+            "Custom Only Auth",
+            None,
+        )
+        .unwrap();
+    let is_authorized = |msg: CosmosMsg<TokenFactoryMsg>| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                contract_addr.clone(),
+                &AuthoriazationQueryMsg::IsAuthorized::<Empty, TokenFactoryMsg> {
+                    msgs: vec![msg],
+                    context: Addr::unchecked("someone").into(),
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+
+    assert!(is_authorized(CosmosMsg::Custom(TokenFactoryMsg::Mint {
+        denom: "factory/ujuno".to_string(),
+    })));
+    assert!(!is_authorized(
+        BankMsg::Send {
+            to_address: "you".to_string(),
+            amount: vec![coin(1, "juno")],
+        }
+        .into()
+    ));
+}
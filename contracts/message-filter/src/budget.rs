@@ -0,0 +1,147 @@
+use cosmwasm_std::{
+    BankMsg, Coin, CosmosMsg, CustomMsg, IbcMsg, StakingMsg, Timestamp, Uint128, WasmMsg,
+};
+use prost::Message;
+
+use crate::decoders::proto;
+use crate::state::{Budget, Spend};
+
+/// The coins a message moves out of the sender's account, or None if they
+/// can't be worked out. That is the case for custom messages and for stargate
+/// messages other than the bank, staking and IBC transfer ones this contract
+/// decodes.
+pub fn msg_spend<C: CustomMsg>(msg: &CosmosMsg<C>) -> Option<Vec<Coin>> {
+    match msg {
+        CosmosMsg::Bank(BankMsg::Send { amount, .. }) => Some(amount.clone()),
+        CosmosMsg::Staking(StakingMsg::Delegate { amount, .. }) => Some(vec![amount.clone()]),
+        CosmosMsg::Ibc(IbcMsg::Transfer { amount, .. }) => Some(vec![amount.clone()]),
+        CosmosMsg::Wasm(WasmMsg::Execute { funds, .. })
+        | CosmosMsg::Wasm(WasmMsg::Instantiate { funds, .. }) => Some(funds.clone()),
+        CosmosMsg::Stargate { type_url, value } => stargate_spend(type_url, value.as_slice()),
+        CosmosMsg::Custom(_) => None,
+        _ => Some(vec![]),
+    }
+}
+
+/// What the coins of a decoded stargate message add up to
+fn stargate_spend(type_url: &str, value: &[u8]) -> Option<Vec<Coin>> {
+    let coins: Vec<proto::Coin> = match type_url {
+        "/cosmos.bank.v1beta1.MsgSend" => proto::MsgSend::decode(value).ok()?.amount,
+        "/cosmos.bank.v1beta1.MsgMultiSend" => proto::MsgMultiSend::decode(value)
+            .ok()?
+            .inputs
+            .into_iter()
+            .flat_map(|input| input.coins)
+            .collect(),
+        "/cosmos.staking.v1beta1.MsgDelegate" => proto::MsgDelegate::decode(value)
+            .ok()?
+            .amount
+            .into_iter()
+            .collect(),
+        "/ibc.applications.transfer.v1.MsgTransfer" => proto::MsgTransfer::decode(value)
+            .ok()?
+            .token
+            .into_iter()
+            .collect(),
+        "/cosmos.staking.v1beta1.MsgUndelegate" | "/cosmos.staking.v1beta1.MsgBeginRedelegate" => {
+            vec![]
+        }
+        _ => return None,
+    };
+    let mut total = vec![];
+    for coin in coins {
+        let amount = coin.amount.parse::<Uint128>().ok()?;
+        add_coins(&mut total, &[Coin::new(amount.u128(), coin.denom)]);
+    }
+    Some(total)
+}
+
+/// Adds coins to a list of totals per denom
+pub fn add_coins(totals: &mut Vec<Coin>, coins: &[Coin]) {
+    for coin in coins {
+        match totals.iter_mut().find(|total| total.denom == coin.denom) {
+            Some(total) => total.amount = total.amount.saturating_add(coin.amount),
+            None => totals.push(coin.clone()),
+        }
+    }
+}
+
+/// Whether a spend is still in the current period of the budget
+pub fn is_current(budget: &Budget, spend: &Spend, now: Timestamp) -> bool {
+    match budget.period {
+        Some(period) => now < spend.period_start.plus_seconds(period),
+        None => true,
+    }
+}
+
+/// What can still be spent of each denom in the budget
+pub fn remaining(budget: &Budget, spend: Option<&Spend>, now: Timestamp) -> Vec<Coin> {
+    let spent = match spend {
+        Some(spend) if is_current(budget, spend, now) => spend.amounts.as_slice(),
+        _ => &[],
+    };
+    budget
+        .limits
+        .iter()
+        .map(|limit| {
+            let spent = spent
+                .iter()
+                .find(|coin| coin.denom == limit.denom)
+                .map(|coin| coin.amount)
+                .unwrap_or_default();
+            Coin {
+                denom: limit.denom.clone(),
+                amount: limit.amount.saturating_sub(spent),
+            }
+        })
+        .collect()
+}
+
+/// Whether the coins fit in what remains. Denoms without a limit can't be
+/// spent.
+pub fn fits(remaining: &[Coin], coins: &[Coin]) -> bool {
+    let mut totals = vec![];
+    add_coins(&mut totals, coins);
+    totals.iter().all(|coin| {
+        remaining
+            .iter()
+            .any(|left| left.denom == coin.denom && left.amount >= coin.amount)
+    })
+}
+
+/// Subtracts coins that are known to fit in what remains
+pub fn consume(remaining: &mut [Coin], coins: &[Coin]) {
+    for coin in coins {
+        if let Some(left) = remaining.iter_mut().find(|left| left.denom == coin.denom) {
+            left.amount = left.amount.saturating_sub(coin.amount);
+        }
+    }
+}
+
+/// What a matcher can still authorize while a request is evaluated: the rest
+/// of its budget and of its uses
+pub struct Allowance {
+    pub coins: Option<Vec<Coin>>,
+    pub uses: Option<u64>,
+}
+
+impl Allowance {
+    /// A budget can't cover a spend that isn't known
+    pub fn covers(&self, spend: Option<&[Coin]>) -> bool {
+        let coins_fit = match (&self.coins, spend) {
+            (Some(coins), Some(spend)) => fits(coins, spend),
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        coins_fit && self.uses != Some(0)
+    }
+
+    pub fn consume(&mut self, spend: Option<&[Coin]>) {
+        if let (Some(coins), Some(spend)) = (&mut self.coins, spend) {
+            consume(coins, spend);
+        }
+        if let Some(uses) = &mut self.uses {
+            *uses = uses.saturating_sub(1);
+        }
+    }
+}
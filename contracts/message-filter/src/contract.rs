@@ -1,92 +1,396 @@
-use cosmwasm_std::{entry_point, to_binary, Addr, CosmosMsg};
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use cw2::set_contract_version;
-use cw_authorizations::msg::{AuthoriazationExecuteMsg, AuthoriazationQueryMsg};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+use cosmwasm_std::{to_binary, Addr, Coin, CosmosMsg, CustomMsg, Empty};
+use cosmwasm_std::{
+    Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage,
+};
+use cw_authorizations::migration::Version;
+use cw_authorizations::msg::{AuthorizationContext, IsAuthorizedResponse};
 use cw_authorizations::{Authorization, AuthorizationError};
+use cw_authorizations_derive::authorization_contract;
 
-use crate::msg::{AuthorizationsResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{Config, Kind, Matcher, MessageFilterState};
-use crate::utils::{deep_partial_match, msg_to_value, str_to_value};
+use crate::budget::{self, add_coins, msg_spend, Allowance};
+use crate::decoders::{Decoder, DecoderRegistry};
+use crate::migrations::migrate_from_v0_1;
+use crate::msg::{
+    AuthorizationsResponse, BudgetStatus, BudgetsResponse, ExecuteMsg, InstantiateMsg, MigrateMsg,
+    QueryMsg,
+};
+use crate::pattern::Pattern;
+use crate::state::{
+    Budget, Config, ConflictStrategy, Effect, EvaluationMode, Matcher, MessageFilterState, Spend,
+};
+use crate::utils::{decode_binaries, msg_to_value, str_to_value, Value};
 use crate::MessageFilterError;
 
-const CONTRACT_NAME: &str = "crates.io:message-filter";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-
-pub struct MessageFilterContract {
+/// The message filter authorization. `C` is the custom message type of the
+/// chain, so that matchers can also be used on custom messages.
+pub struct MessageFilterContract<C = Empty> {
     state: MessageFilterState,
+    decoders: DecoderRegistry,
+    custom: PhantomData<C>,
 }
 
-impl MessageFilterContract {
+impl<C: CustomMsg> MessageFilterContract<C> {
+    /// Registers a decoder for stargate messages with the given type url
+    pub fn with_decoder(mut self, type_url: &str, decoder: Decoder) -> Self {
+        self.decoders = self.decoders.with_decoder(type_url, decoder);
+        self
+    }
+
     pub fn instantiate(
         &self,
         deps: DepsMut,
         msg: InstantiateMsg,
-    ) -> Result<(), AuthorizationError> {
+    ) -> Result<(), AuthorizationError<MessageFilterError>> {
         let config = Config {
-            parent: msg.parent,
             kind: msg.kind,
+            decode_binaries: msg.decode_binaries,
+            conflict_strategy: msg.conflict_strategy,
+            evaluation_mode: msg.evaluation_mode,
+            updaters: vec![],
         };
         self.state.config.save(deps.storage, &config)?;
+        self.state.ownership.initialize(deps.storage, msg.owner)?;
         Ok(())
     }
+
+    pub fn migrate(
+        &self,
+        deps: DepsMut,
+        _msg: MigrateMsg,
+        from_version: &Version,
+    ) -> Result<(), AuthorizationError<MessageFilterError>> {
+        if *from_version < Version::new(0, 2, 0) {
+            migrate_from_v0_1(deps.storage, &self.state)?;
+        }
+        Ok(())
+    }
+
+    /// Checks msgs against the sender's matchers (including the global ones),
+    /// following the evaluation mode of the config. Returns what the messages
+    /// spend from the budgets of the matchers that authorized them.
+    fn check_msgs<'a>(
+        &self,
+        deps: Deps,
+        env: &Env,
+        sender: &Addr,
+        config: &Config,
+        auths: &'a [Matcher],
+        msgs: &[CosmosMsg<C>],
+    ) -> Result<Approvals<'a>, AuthorizationError<MessageFilterError>> {
+        // If there are no auths, return the default for each Kind
+        if auths.is_empty() {
+            return config.default_authorization().map(|_| vec![]);
+        }
+
+        // check that all messages can be converted to values, once per query
+        let to_error = |e: StdError| {
+            AuthorizationError::ContractError(MessageFilterError::UnauthorizedBecause {
+                reason: e.to_string(),
+            })
+        };
+        let msg_values = msgs
+            .iter()
+            .map(|m| {
+                let value = msg_to_value(m)?;
+                if config.decode_binaries {
+                    Ok(decode_binaries(self.decoders.decode_stargate(value)?))
+                } else {
+                    Ok(value)
+                }
+            })
+            .collect::<StdResult<Vec<_>>>()
+            .map_err(to_error)?;
+
+        // What is left of each budget and use count. The messages of a
+        // request that are authorized by the same matcher use it up together.
+        let mut remaining = BTreeMap::new();
+        for auth in auths {
+            if auth.budget.is_none() && auth.max_uses.is_none() {
+                continue;
+            }
+            let coins = match &auth.budget {
+                Some(budget) => {
+                    let spend = self
+                        .state
+                        .spends
+                        .may_load(deps.storage, (auth.id, sender.clone()))?;
+                    Some(budget::remaining(budget, spend.as_ref(), env.block.time))
+                }
+                None => None,
+            };
+            remaining.insert(
+                auth.id,
+                Allowance {
+                    coins,
+                    uses: auth.max_uses,
+                },
+            );
+        }
+
+        // An unknown spend is only approved by matchers without a budget,
+        // which don't record it
+        let mut approvals = vec![];
+        match config.evaluation_mode {
+            EvaluationMode::SingleMatcher {} => {
+                let spend = total_spend(msgs);
+                let matcher =
+                    self.decide(config, auths, &mut remaining, spend.as_deref(), |a| {
+                        matches_request(config, a, &msg_values)
+                    })?;
+                approvals.extend(matcher.map(|matcher| (matcher, spend.unwrap_or_default())));
+            }
+            EvaluationMode::PerMessage {} => {
+                for (msg, value) in msgs.iter().zip(&msg_values) {
+                    let spend = msg_spend(msg);
+                    let matcher =
+                        self.decide(config, auths, &mut remaining, spend.as_deref(), |a| {
+                            a.pattern.matches(value)
+                        })?;
+                    approvals.extend(matcher.map(|matcher| (matcher, spend.unwrap_or_default())));
+                }
+            }
+            EvaluationMode::Sequence {} => {
+                let spend = total_spend(msgs);
+                // Only array matchers describe a sequence. The others, deny
+                // rules included, are checked against each message.
+                let batch = Value::Array(msg_values.clone());
+                let matcher = self.decide(
+                    config,
+                    auths,
+                    &mut remaining,
+                    spend.as_deref(),
+                    |a| match a.pattern {
+                        Pattern::Array(_) => a.pattern.matches(&batch),
+                        _ => matches_request(config, a, &msg_values),
+                    },
+                )?;
+                approvals.extend(matcher.map(|matcher| (matcher, spend.unwrap_or_default())));
+            }
+        }
+        Ok(approvals)
+    }
+
+    /// Decides based on the auths for which `matches` is true. When matchers
+    /// with different effects match, the conflict strategy decides. Allow
+    /// matchers whose budget can't cover the spend (or the spend is unknown),
+    /// or that have no uses left, are skipped. Returns the matcher that authorized the messages if
+    /// it has a budget or a use count.
+    fn decide<'a>(
+        &self,
+        config: &Config,
+        auths: &'a [Matcher],
+        remaining: &mut BTreeMap<u64, Allowance>,
+        spend: Option<&[Coin]>,
+        matches: impl Fn(&Matcher) -> bool,
+    ) -> Result<Option<&'a Matcher>, AuthorizationError<MessageFilterError>> {
+        let within_budget = |a: &Matcher| match (config.effect(a), remaining.get(&a.id)) {
+            (Effect::Allow {}, Some(left)) => left.covers(spend),
+            _ => true,
+        };
+        let (mut matched, over_budget): (Vec<&Matcher>, Vec<&Matcher>) = auths
+            .iter()
+            .filter(|a| matches(a))
+            .partition(|a| within_budget(a));
+        // The sort is stable, so matchers with the same priority keep their order
+        matched.sort_by_key(|a| Reverse(a.priority));
+        let with_effect = |effect: Effect| {
+            matched
+                .iter()
+                .find(|a| config.effect(a) == effect)
+                .or_else(|| matched.first())
+        };
+        let decisive = match config.conflict_strategy {
+            ConflictStrategy::DenyOverrides {} => with_effect(Effect::Deny {}),
+            ConflictStrategy::AllowOverrides {} => with_effect(Effect::Allow {}),
+            ConflictStrategy::FirstMatch {} => matched.first(),
+        };
+
+        if let Some(matcher) = decisive {
+            return match config.effect(matcher) {
+                Effect::Allow {} => match remaining.get_mut(&matcher.id) {
+                    Some(left) => {
+                        left.consume(spend);
+                        Ok(Some(*matcher))
+                    }
+                    None => Ok(None),
+                },
+                Effect::Deny {} => Err(AuthorizationError::ContractError(
+                    MessageFilterError::RejectedByMatcher {
+                        matcher: matcher.matcher.clone(),
+                    },
+                )),
+            };
+        }
+        match (config.default_authorization(), over_budget.first()) {
+            (Err(_), Some(matcher)) => Err(AuthorizationError::ContractError(
+                MessageFilterError::BudgetExceeded {
+                    matcher: matcher.matcher.clone(),
+                },
+            )),
+            (result, _) => result.map(|_| None),
+        }
+    }
+}
+
+/// The matchers with a budget or a use count that authorized some messages,
+/// with what those messages spend
+type Approvals<'a> = Vec<(&'a Matcher, Vec<Coin>)>;
+
+/// Whether `matcher` matches the messages of a request as a whole. Allow
+/// matchers must match every message, while deny matchers match the request
+/// if they match any of its messages.
+fn matches_request(config: &Config, matcher: &Matcher, msg_values: &[Value]) -> bool {
+    match config.effect(matcher) {
+        Effect::Allow {} => msg_values.iter().all(|m| matcher.pattern.matches(m)),
+        Effect::Deny {} => msg_values.iter().any(|m| matcher.pattern.matches(m)),
+    }
 }
 
-impl Authorization<ExecuteMsg, QueryMsg, MessageFilterError> for MessageFilterContract {
+/// What the messages spend together, or None if the spend of any of them
+/// can't be worked out
+fn total_spend<C: CustomMsg>(msgs: &[CosmosMsg<C>]) -> Option<Vec<Coin>> {
+    let mut total = vec![];
+    for msg in msgs {
+        add_coins(&mut total, &msg_spend(msg)?);
+    }
+    Some(total)
+}
+
+#[authorization_contract(
+    instantiate = InstantiateMsg,
+    execute = ExecuteMsg,
+    query = QueryMsg,
+    error = MessageFilterError,
+    migrate = MigrateMsg
+)]
+impl<C: CustomMsg> Authorization<ExecuteMsg, QueryMsg, MessageFilterError, C>
+    for MessageFilterContract<C>
+{
     fn new() -> Self {
         MessageFilterContract {
             state: MessageFilterState::new(),
+            decoders: DecoderRegistry::default(),
+            custom: PhantomData,
         }
     }
 
+    fn contract_type(&self) -> String {
+        "message_filter".to_string()
+    }
+
     fn is_authorized(
         &self,
         deps: Deps,
-        msgs: &Vec<CosmosMsg>,
-        sender: &Addr,
+        env: &Env,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
     ) -> Result<bool, AuthorizationError<MessageFilterError>> {
         let config = self.state.config.load(deps.storage)?;
-        let auths = self.state.matchers.load(deps.storage, sender.clone());
+        let auths = self.state.sender_matchers(deps.storage, &context.sender)?;
+        self.check_msgs(deps, env, &context.sender, &config, &auths, msgs)
+            .map(|_| true)
+    }
 
-        // If there are no auths, return the default for each Kind
-        if auths.is_err() {
-            return Ok(config.default_authorization());
-        }
+    fn is_authorized_per_message(
+        &self,
+        deps: Deps,
+        env: &Env,
+        msgs: &[CosmosMsg<C>],
+        context: &AuthorizationContext,
+    ) -> Result<Vec<IsAuthorizedResponse>, AuthorizationError<MessageFilterError>> {
+        // Load the state once instead of once per message
+        let config = self.state.config.load(deps.storage)?;
+        let auths = self.state.sender_matchers(deps.storage, &context.sender)?;
+        Ok(msgs
+            .iter()
+            .map(|msg| {
+                IsAuthorizedResponse::from_result(
+                    self.check_msgs(
+                        deps,
+                        env,
+                        &context.sender,
+                        &config,
+                        &auths,
+                        std::slice::from_ref(msg),
+                    )
+                    .map(|_| true),
+                )
+            })
+            .collect())
+    }
 
-        let auths = auths.unwrap();
+    fn is_stateful(&self) -> bool {
+        true
+    }
 
-        // check that all messages can be converted to values
-        for m in msgs {
-            msg_to_value(&m).map_err(|e| {
-                AuthorizationError::ContractError(MessageFilterError::UnauthorizedBecause {
-                    reason: e.to_string(),
-                })
-            })?;
-        }
-        // check that all auths can be converted to values
-        for a in &auths {
-            str_to_value(&a.matcher).map_err(|e| {
-                AuthorizationError::ContractError(MessageFilterError::UnauthorizedBecause {
-                    reason: e.to_string(),
-                })
-            })?;
+    /// Records what the messages spend from the budgets of the matchers that
+    /// authorized them and uses up those with a use count. Matchers with no
+    /// uses left are removed. Messages that this filter doesn't authorize
+    /// don't use anything. Only updaters can use up global matchers.
+    fn update_own_state(
+        &self,
+        deps: DepsMut,
+        env: &Env,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
+        original_sender: &Addr,
+    ) -> Result<Response, AuthorizationError<MessageFilterError>> {
+        let config = self.state.config.load(deps.storage)?;
+        let auths = self.state.sender_matchers(deps.storage, &context.sender)?;
+        let approvals =
+            match self.check_msgs(deps.as_ref(), env, &context.sender, &config, &auths, msgs) {
+                Ok(approvals) if !approvals.is_empty() => approvals,
+                _ => return Ok(Response::default()),
+            };
+        let is_updater = config.updaters.contains(original_sender);
+        if original_sender != &context.sender && !is_updater {
+            return Err(AuthorizationError::Unauthorized {
+                reason: Some(
+                    "Only the sender or an updater can record executions for a sender".to_string(),
+                ),
+            });
         }
 
-        let matched = auths.iter().any(|a| {
-            msgs.iter().all(|m| {
-                deep_partial_match(
-                    &msg_to_value(&m).unwrap(),
-                    &str_to_value(&a.matcher).unwrap(),
-                )
-            })
-        });
+        // Uses of global matchers are shared by all senders, so a sender
+        // recording its own executions can't take them
+        if !is_updater
+            && approvals
+                .iter()
+                .any(|(matcher, _)| matcher.addr.is_none() && matcher.max_uses.is_some())
+        {
+            return Err(AuthorizationError::Unauthorized {
+                reason: Some("Only an updater can use up global matchers".to_string()),
+            });
+        }
 
-        if matched {
-            return match config.kind {
-                Kind::Allow {} => Ok(true),
-                Kind::Reject {} => Ok(false),
+        let mut response = Response::default().add_attribute("action", "record_execution");
+        for (matcher, spent) in approvals {
+            if matcher.max_uses.is_some() {
+                response = self.use_matcher(deps.storage, matcher, response)?;
+            }
+            let budget = match &matcher.budget {
+                Some(budget) => budget,
+                None => continue,
             };
+            let key = (matcher.id, context.sender.clone());
+            let mut spend = self
+                .state
+                .spends
+                .may_load(deps.storage, key.clone())?
+                .filter(|spend| budget::is_current(budget, spend, env.block.time))
+                .unwrap_or(Spend {
+                    period_start: env.block.time,
+                    amounts: vec![],
+                });
+            add_coins(&mut spend.amounts, &spent);
+            self.state.spends.save(deps.storage, key, &spend)?;
+            response = response.add_attribute("matcher_id", matcher.id.to_string());
         }
-        Ok(config.default_authorization())
+        Ok(response)
     }
 
     fn get_sub_authorizations(
@@ -103,27 +407,77 @@ impl Authorization<ExecuteMsg, QueryMsg, MessageFilterError> for MessageFilterCo
         info: MessageInfo,
         msg: ExecuteMsg,
     ) -> Result<Response, AuthorizationError<MessageFilterError>> {
-        let config = self.state.config.load(deps.storage)?;
-        if info.sender != config.parent {
-            return Err(AuthorizationError::ContractError(
-                MessageFilterError::UnauthorizedBecause {
-                    reason: "Only the parent can add or remove authorizations on this contract"
-                        .to_string(),
-                },
-            ));
-        }
+        self.state
+            .ownership
+            .assert_owner(deps.storage, &info.sender)?;
 
         match msg {
-            ExecuteMsg::AddAuthorization { addr, msg } => {
-                self.execute_add_authorization(deps, info, addr, msg)
-            }
+            ExecuteMsg::AddAuthorization {
+                addr,
+                msg,
+                effect,
+                priority,
+                budget,
+                max_uses,
+            } => self.execute_add_authorization(
+                deps,
+                Some(addr),
+                msg,
+                effect,
+                priority,
+                budget,
+                max_uses,
+            ),
             ExecuteMsg::RemoveAuthorization { addr, msg } => {
-                self.execute_remove_authorization(deps, info, addr, msg)
+                self.execute_remove_authorization(deps, info, Some(addr), msg)
+            }
+            ExecuteMsg::AddGlobalAuthorization {
+                msg,
+                effect,
+                priority,
+                budget,
+                max_uses,
+            } => {
+                self.execute_add_authorization(deps, None, msg, effect, priority, budget, max_uses)
+            }
+            ExecuteMsg::RemoveGlobalAuthorization { msg } => {
+                self.execute_remove_authorization(deps, info, None, msg)
+            }
+            ExecuteMsg::SetUpdaters { updaters } => {
+                let updaters = updaters
+                    .iter()
+                    .map(|addr| deps.api.addr_validate(addr))
+                    .collect::<StdResult<Vec<_>>>()?;
+                self.state
+                    .config
+                    .update(deps.storage, |mut config| -> StdResult<_> {
+                        config.updaters = updaters;
+                        Ok(config)
+                    })?;
+                Ok(Response::default().add_attribute("action", "set_updaters"))
+            }
+            ExecuteMsg::SetEvaluationMode { mode } => {
+                self.state
+                    .config
+                    .update(deps.storage, |mut config| -> StdResult<_> {
+                        config.evaluation_mode = mode;
+                        Ok(config)
+                    })?;
+                Ok(Response::default().add_attribute("action", "set_evaluation_mode"))
+            }
+            ExecuteMsg::SetConflictStrategy { strategy } => {
+                self.state
+                    .config
+                    .update(deps.storage, |mut config| -> StdResult<_> {
+                        config.conflict_strategy = strategy;
+                        Ok(config)
+                    })?;
+                Ok(Response::default().add_attribute("action", "set_conflict_strategy"))
             }
         }
     }
 
-    fn query_extension(&self, deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    fn query_extension(&self, deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         match msg {
             QueryMsg::GetAuthorizations { sender } => {
                 let auths = self.state.matchers.may_load(deps.storage, sender)?;
@@ -134,100 +488,160 @@ impl Authorization<ExecuteMsg, QueryMsg, MessageFilterError> for MessageFilterCo
                     }),
                 }
             }
+            QueryMsg::GetGlobalAuthorizations {} => to_binary(&AuthorizationsResponse {
+                authorizations: self
+                    .state
+                    .global_matchers
+                    .may_load(deps.storage)?
+                    .unwrap_or_default(),
+            }),
+            QueryMsg::GetBudgets { sender } => {
+                let auths = self.state.sender_matchers(deps.storage, &sender)?;
+                let budgets = auths
+                    .iter()
+                    .filter_map(|auth| auth.budget.as_ref().map(|budget| (auth, budget)))
+                    .map(|(auth, budget)| {
+                        let spend = self
+                            .state
+                            .spends
+                            .may_load(deps.storage, (auth.id, sender.clone()))?
+                            .filter(|spend| budget::is_current(budget, spend, env.block.time));
+                        Ok(BudgetStatus {
+                            matcher_id: auth.id,
+                            matcher: auth.matcher.clone(),
+                            remaining: budget::remaining(budget, spend.as_ref(), env.block.time),
+                            resets_at: match (&spend, budget.period) {
+                                (Some(spend), Some(period)) => {
+                                    Some(spend.period_start.plus_seconds(period))
+                                }
+                                _ => None,
+                            },
+                        })
+                    })
+                    .collect::<StdResult<Vec<_>>>()?;
+                to_binary(&BudgetsResponse { budgets })
+            }
         }
     }
 }
 
-impl MessageFilterContract {
+impl<C: CustomMsg> MessageFilterContract<C> {
+    /// Adds a matcher for authorized_addr, or a global one if there's no address
+    #[allow(clippy::too_many_arguments)]
     fn execute_add_authorization(
         &self,
         deps: DepsMut,
-        _info: MessageInfo,
-        authorized_addr: Addr,
+        authorized_addr: Option<Addr>,
         authorization_matcher: String,
+        effect: Option<Effect>,
+        priority: Option<u32>,
+        budget: Option<Budget>,
+        max_uses: Option<u64>,
     ) -> Result<Response, AuthorizationError<MessageFilterError>> {
-        // If the message can't be converted to a string, we fail
-        str_to_value(&authorization_matcher)?;
-        self.state.matchers.update(
-            deps.storage,
-            authorized_addr.clone(),
-            |auth: Option<Vec<Matcher>>| -> Result<Vec<Matcher>, AuthorizationError<MessageFilterError>> {
-                let new_auth = Matcher {
-                    addr: authorized_addr,
-                    matcher: authorization_matcher,
-                };
-                match auth {
-                    Some(mut auth) => {
-                        auth.push(new_auth);
-                        Ok(auth)
-                    }
-                    None => Ok(vec![new_auth]),
-                }
-            },
-        )?;
+        // If the message can't be converted to a string, we fail. The matcher
+        // is stored parsed, so it doesn't need to be parsed again on every query
+        let value = str_to_value(&authorization_matcher)?;
+        let pattern = Pattern::parse_msg_matcher(&value).map_err(|reason| {
+            AuthorizationError::ContractError(MessageFilterError::InvalidMatcher { reason })
+        })?;
+        let invalid = |reason: &str| {
+            AuthorizationError::ContractError(MessageFilterError::InvalidMatcher {
+                reason: reason.to_string(),
+            })
+        };
+        let config = self.state.config.load(deps.storage)?;
+        if let Some(budget) = &budget {
+            if effect.unwrap_or(config.effect_of_kind()) != (Effect::Allow {}) {
+                return Err(invalid("Only allow matchers can have a budget"));
+            }
+            if budget.period == Some(0) {
+                return Err(invalid("The budget period can't be 0"));
+            }
+        }
+        if let Some(max_uses) = max_uses {
+            if effect.unwrap_or(config.effect_of_kind()) != (Effect::Allow {}) {
+                return Err(invalid("Only allow matchers can have a use count"));
+            }
+            if max_uses == 0 {
+                return Err(invalid("A matcher needs at least one use"));
+            }
+        }
+
+        let mut auth = self
+            .state
+            .may_load_matchers(deps.storage, authorized_addr.as_ref())?
+            .unwrap_or_default();
+        auth.push(Matcher {
+            addr: authorized_addr.clone(),
+            matcher: authorization_matcher,
+            pattern,
+            effect,
+            priority: priority.unwrap_or_default(),
+            id: self.state.next_matcher_id(deps.storage)?,
+            budget,
+            max_uses,
+        });
+        self.state
+            .save_matchers(deps.storage, authorized_addr.as_ref(), &auth)?;
 
         Ok(Response::default().add_attribute("action", "allow_message"))
     }
 
+    /// Uses up one use of a matcher, removing it when none are left. Several
+    /// messages of a request that are authorized separately can use the
+    /// matcher more than once.
+    fn use_matcher(
+        &self,
+        storage: &mut dyn Storage,
+        matcher: &Matcher,
+        response: Response,
+    ) -> Result<Response, AuthorizationError<MessageFilterError>> {
+        let mut auth = self
+            .state
+            .may_load_matchers(storage, matcher.addr.as_ref())?
+            .unwrap_or_default();
+        let i = match auth.iter().position(|a| a.id == matcher.id) {
+            Some(i) => i,
+            // Already removed by an earlier message of the request
+            None => return Ok(response),
+        };
+        let response = match auth[i].max_uses {
+            Some(uses) if uses > 1 => {
+                auth[i].max_uses = Some(uses - 1);
+                response
+            }
+            _ => {
+                auth.remove(i);
+                response.add_attribute("removed_matcher_id", matcher.id.to_string())
+            }
+        };
+        self.state
+            .save_matchers(storage, matcher.addr.as_ref(), &auth)?;
+        Ok(response)
+    }
+
     fn execute_remove_authorization(
         &self,
         deps: DepsMut,
         _info: MessageInfo,
-        authorized_addr: Addr,
+        authorized_addr: Option<Addr>,
         authorization_matcher: String,
     ) -> Result<Response, AuthorizationError<MessageFilterError>> {
-        self.state.matchers.update(
-            deps.storage,
-            authorized_addr.clone(),
-            |auth: Option<Vec<Matcher>>| -> Result<Vec<Matcher>, AuthorizationError<MessageFilterError>> {
-                match auth {
-                    Some(mut auth) => {
-                        let i = auth
-                            .iter()
-                            .position(|x| *x.matcher == authorization_matcher);
-                        if i.is_none() {
-                            return Err(AuthorizationError::ContractError(
-                                MessageFilterError::NotFound {},
-                            ))
-                        }
-                        auth.remove(i.unwrap());
-                        Ok(auth)
-                    }
-                    None => {
-                        Err(AuthorizationError::ContractError(
-                            MessageFilterError::NotFound {},
-                        ))
-                    }
-                }
-            },
-        )?;
+        let mut auth = self
+            .state
+            .may_load_matchers(deps.storage, authorized_addr.as_ref())?
+            .ok_or(AuthorizationError::ContractError(
+                MessageFilterError::NotFound {},
+            ))?;
+        let i = auth
+            .iter()
+            .position(|x| *x.matcher == authorization_matcher)
+            .ok_or(AuthorizationError::ContractError(
+                MessageFilterError::NotFound {},
+            ))?;
+        auth.remove(i);
+        self.state
+            .save_matchers(deps.storage, authorized_addr.as_ref(), &auth)?;
         Ok(Response::default().add_attribute("action", "removed"))
     }
 }
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    msg: InstantiateMsg,
-) -> Result<Response, AuthorizationError> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    MessageFilterContract::new().instantiate(deps, msg)?;
-    Ok(Response::default().add_attribute("action", "instantiate"))
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: AuthoriazationExecuteMsg<ExecuteMsg>,
-) -> Result<Response, AuthorizationError<MessageFilterError>> {
-    MessageFilterContract::new().execute(deps, env, info, msg)
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: AuthoriazationQueryMsg<QueryMsg>) -> StdResult<Binary> {
-    MessageFilterContract::new().query(deps, env, msg)
-}
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Binary, StdError, StdResult};
+use serde::Serialize;
+use serde_json_wasm::to_string;
+
+use crate::utils::{str_to_value, Value};
+
+/// Turns the protobuf encoded value of a stargate message into a json Value
+pub type Decoder = fn(&[u8]) -> StdResult<Value>;
+
+/// Decodes protobuf messages into their json Value based on their type url.
+///
+/// The default registry knows about some common bank, staking and IBC
+/// transfer messages. More can be added with `with_decoder`.
+pub struct DecoderRegistry {
+    decoders: BTreeMap<String, Decoder>,
+}
+
+impl DecoderRegistry {
+    /// A registry without any decoders
+    pub fn empty() -> Self {
+        DecoderRegistry {
+            decoders: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_decoder(mut self, type_url: &str, decoder: Decoder) -> Self {
+        self.decoders.insert(type_url.to_string(), decoder);
+        self
+    }
+
+    pub fn decode(&self, type_url: &str, value: &[u8]) -> Option<StdResult<Value>> {
+        self.decoders.get(type_url).map(|decoder| decoder(value))
+    }
+
+    /// Replaces the base64 value of a `{"stargate": {"type_url": ..., "value": ...}}`
+    /// message with its decoded json. Messages of any other kind, or with an
+    /// unknown type url, are returned untouched.
+    pub fn decode_stargate(&self, msg: Value) -> StdResult<Value> {
+        let mut msg = match msg {
+            Value::Object(msg) => msg,
+            msg => return Ok(msg),
+        };
+        if let Some(Value::Object(stargate)) = msg.get_mut("stargate") {
+            let decoded = match (stargate.get("type_url"), stargate.get("value")) {
+                (Some(Value::String(type_url)), Some(Value::String(value))) => {
+                    let bytes = Binary::from_base64(value)?;
+                    self.decode(type_url, bytes.as_slice()).transpose()?
+                }
+                _ => None,
+            };
+            if let Some(decoded) = decoded {
+                stargate.insert("value".to_string(), decoded);
+            }
+        }
+        Ok(Value::Object(msg))
+    }
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        DecoderRegistry::empty()
+            .with_decoder(
+                "/cosmos.bank.v1beta1.MsgSend",
+                decode_proto::<proto::MsgSend>,
+            )
+            .with_decoder(
+                "/cosmos.bank.v1beta1.MsgMultiSend",
+                decode_proto::<proto::MsgMultiSend>,
+            )
+            .with_decoder(
+                "/cosmos.staking.v1beta1.MsgDelegate",
+                decode_proto::<proto::MsgDelegate>,
+            )
+            .with_decoder(
+                "/cosmos.staking.v1beta1.MsgUndelegate",
+                decode_proto::<proto::MsgUndelegate>,
+            )
+            .with_decoder(
+                "/cosmos.staking.v1beta1.MsgBeginRedelegate",
+                decode_proto::<proto::MsgBeginRedelegate>,
+            )
+            .with_decoder(
+                "/ibc.applications.transfer.v1.MsgTransfer",
+                decode_proto::<proto::MsgTransfer>,
+            )
+    }
+}
+
+/// Decodes any prost message that can also be serialized to json. This can be
+/// used to register decoders for other types.
+pub fn decode_proto<T: prost::Message + Default + Serialize>(value: &[u8]) -> StdResult<Value> {
+    let msg = T::decode(value).map_err(|e| StdError::ParseErr {
+        target_type: std::any::type_name::<T>().to_string(),
+        msg: e.to_string(),
+    })?;
+    let serialized = to_string(&msg).map_err(|e| StdError::SerializeErr {
+        source_type: std::any::type_name::<T>().to_string(),
+        msg: e.to_string(),
+    })?;
+    str_to_value(&serialized)
+}
+
+/// The protobuf messages known by the default registry
+pub mod proto {
+    use serde::Serialize;
+
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct Coin {
+        #[prost(string, tag = "1")]
+        pub denom: String,
+        #[prost(string, tag = "2")]
+        pub amount: String,
+    }
+
+    /// /cosmos.bank.v1beta1.MsgSend
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct MsgSend {
+        #[prost(string, tag = "1")]
+        pub from_address: String,
+        #[prost(string, tag = "2")]
+        pub to_address: String,
+        #[prost(message, repeated, tag = "3")]
+        pub amount: Vec<Coin>,
+    }
+
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct Input {
+        #[prost(string, tag = "1")]
+        pub address: String,
+        #[prost(message, repeated, tag = "2")]
+        pub coins: Vec<Coin>,
+    }
+
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct Output {
+        #[prost(string, tag = "1")]
+        pub address: String,
+        #[prost(message, repeated, tag = "2")]
+        pub coins: Vec<Coin>,
+    }
+
+    /// /cosmos.bank.v1beta1.MsgMultiSend
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct MsgMultiSend {
+        #[prost(message, repeated, tag = "1")]
+        pub inputs: Vec<Input>,
+        #[prost(message, repeated, tag = "2")]
+        pub outputs: Vec<Output>,
+    }
+
+    /// /cosmos.staking.v1beta1.MsgDelegate
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct MsgDelegate {
+        #[prost(string, tag = "1")]
+        pub delegator_address: String,
+        #[prost(string, tag = "2")]
+        pub validator_address: String,
+        #[prost(message, optional, tag = "3")]
+        pub amount: Option<Coin>,
+    }
+
+    /// /cosmos.staking.v1beta1.MsgUndelegate
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct MsgUndelegate {
+        #[prost(string, tag = "1")]
+        pub delegator_address: String,
+        #[prost(string, tag = "2")]
+        pub validator_address: String,
+        #[prost(message, optional, tag = "3")]
+        pub amount: Option<Coin>,
+    }
+
+    /// /cosmos.staking.v1beta1.MsgBeginRedelegate
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct MsgBeginRedelegate {
+        #[prost(string, tag = "1")]
+        pub delegator_address: String,
+        #[prost(string, tag = "2")]
+        pub validator_src_address: String,
+        #[prost(string, tag = "3")]
+        pub validator_dst_address: String,
+        #[prost(message, optional, tag = "4")]
+        pub amount: Option<Coin>,
+    }
+
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct Height {
+        #[prost(uint64, tag = "1")]
+        pub revision_number: u64,
+        #[prost(uint64, tag = "2")]
+        pub revision_height: u64,
+    }
+
+    /// /ibc.applications.transfer.v1.MsgTransfer
+    #[derive(Clone, PartialEq, Serialize, prost::Message)]
+    pub struct MsgTransfer {
+        #[prost(string, tag = "1")]
+        pub source_port: String,
+        #[prost(string, tag = "2")]
+        pub source_channel: String,
+        #[prost(message, optional, tag = "3")]
+        pub token: Option<Coin>,
+        #[prost(string, tag = "4")]
+        pub sender: String,
+        #[prost(string, tag = "5")]
+        pub receiver: String,
+        #[prost(message, optional, tag = "6")]
+        pub timeout_height: Option<Height>,
+        #[prost(uint64, tag = "7")]
+        pub timeout_timestamp: u64,
+        #[prost(string, tag = "8")]
+        pub memo: String,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::{Binary, CosmosMsg};
+    use prost::Message;
+    use serde_json_wasm::from_str;
+
+    use crate::utils::{deep_partial_match, msg_to_value};
+
+    #[test]
+    fn test_decode_stargate() {
+        let send = proto::MsgSend {
+            from_address: "me".to_string(),
+            to_address: "you".to_string(),
+            amount: vec![proto::Coin {
+                denom: "juno".to_string(),
+                amount: "100".to_string(),
+            }],
+        };
+        let msg: CosmosMsg = CosmosMsg::Stargate {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+            value: Binary::from(send.encode_to_vec()),
+        };
+        let value = msg_to_value(&msg).unwrap();
+
+        let matcher = from_str(
+            r#"{"stargate": {"value": {"to_address": "you", "amount": [{"denom": "juno", "amount": "100"}]}}}"#,
+        )
+        .unwrap();
+        let decoded = DecoderRegistry::default()
+            .decode_stargate(value.clone())
+            .unwrap();
+        assert!(deep_partial_match(&decoded, &matcher));
+
+        // Unknown type urls are left untouched
+        let decoded = DecoderRegistry::empty()
+            .decode_stargate(value.clone())
+            .unwrap();
+        assert_eq!(decoded, value);
+
+        // Custom decoders can be registered
+        let registry = DecoderRegistry::empty()
+            .with_decoder("/cosmos.bank.v1beta1.MsgSend", |_| {
+                Ok(from_str(r#"{"custom": true}"#).unwrap())
+            });
+        let decoded = registry.decode_stargate(value).unwrap();
+        assert!(deep_partial_match(
+            &decoded,
+            &from_str(r#"{"stargate": {"value": {"custom": true}}}"#).unwrap()
+        ));
+    }
+}
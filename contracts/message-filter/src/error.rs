@@ -1,3 +1,4 @@
+use cw_authorizations::ErrorCode;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -7,4 +8,30 @@ pub enum MessageFilterError {
 
     #[error("UnauthorizedBecause: {reason:?}")]
     UnauthorizedBecause { reason: String },
+
+    #[error("No authorization matched the messages")]
+    NoMatchingAuthorization {},
+
+    #[error("The messages were rejected by the matcher {matcher}")]
+    RejectedByMatcher { matcher: String },
+
+    #[error("Invalid matcher: {reason}")]
+    InvalidMatcher { reason: String },
+
+    #[error("The messages exceed what the matcher {matcher} still allows")]
+    BudgetExceeded { matcher: String },
+}
+
+impl ErrorCode for MessageFilterError {
+    fn code(&self) -> String {
+        match self {
+            MessageFilterError::NotFound {} => "not_found",
+            MessageFilterError::UnauthorizedBecause { .. } => "unauthorized_because",
+            MessageFilterError::NoMatchingAuthorization {} => "no_matching_authorization",
+            MessageFilterError::RejectedByMatcher { .. } => "rejected_by_matcher",
+            MessageFilterError::InvalidMatcher { .. } => "invalid_matcher",
+            MessageFilterError::BudgetExceeded { .. } => "budget_exceeded",
+        }
+        .to_string()
+    }
 }
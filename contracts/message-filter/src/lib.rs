@@ -1,6 +1,11 @@
+pub mod budget;
 pub mod contract;
+pub mod decoders;
 mod error;
+mod migrations;
 pub mod msg;
+pub mod pattern;
+mod regex;
 pub mod state;
 pub mod utils;
 
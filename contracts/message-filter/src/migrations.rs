@@ -0,0 +1,70 @@
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
+use cw_authorizations::AuthorizationError;
+use cw_storage_plus::{Item, Map};
+use serde::{Deserialize, Serialize};
+
+use crate::pattern::Pattern;
+use crate::state::{Config, ConflictStrategy, EvaluationMode, Kind, Matcher, MessageFilterState};
+use crate::utils::str_to_value;
+use crate::MessageFilterError;
+
+/// Before 0.2.0 the owner was stored in the config as `parent`
+#[derive(Serialize, Deserialize)]
+struct ConfigV0_1 {
+    parent: Addr,
+    kind: Kind,
+}
+
+/// Before 0.2.0 matchers were only stored as strings
+#[derive(Serialize, Deserialize)]
+struct MatcherV0_1 {
+    matcher: String,
+    addr: Addr,
+}
+
+pub fn migrate_from_v0_1(
+    storage: &mut dyn Storage,
+    state: &MessageFilterState,
+) -> Result<(), AuthorizationError<MessageFilterError>> {
+    let old: ConfigV0_1 = Item::new("config").load(storage)?;
+    state.config.save(
+        storage,
+        &Config {
+            kind: old.kind,
+            decode_binaries: false,
+            conflict_strategy: ConflictStrategy::default(),
+            evaluation_mode: EvaluationMode::default(),
+            updaters: vec![],
+        },
+    )?;
+    state.ownership.initialize(storage, old.parent)?;
+
+    let old: Map<Addr, Vec<MatcherV0_1>> = Map::new("matchers");
+    let entries = old
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (sender, matchers) in entries {
+        let matchers = matchers
+            .into_iter()
+            .map(|m| {
+                // Matchers that were accepted before are kept even if they
+                // would be rejected by the stricter validation of new ones
+                let pattern = Pattern::parse(&str_to_value(&m.matcher)?).map_err(|reason| {
+                    AuthorizationError::ContractError(MessageFilterError::InvalidMatcher { reason })
+                })?;
+                Ok(Matcher {
+                    matcher: m.matcher,
+                    addr: Some(m.addr),
+                    pattern,
+                    effect: None,
+                    priority: 0,
+                    id: state.next_matcher_id(storage)?,
+                    budget: None,
+                    max_uses: None,
+                })
+            })
+            .collect::<Result<Vec<_>, AuthorizationError<MessageFilterError>>>()?;
+        state.matchers.save(storage, sender, &matchers)?;
+    }
+    Ok(())
+}
@@ -1,21 +1,68 @@
-use cosmwasm_std::{Addr, CustomMsg};
+use cosmwasm_std::{Addr, Coin, CustomMsg, Timestamp};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::state::{Kind, Matcher};
+use crate::state::{Budget, ConflictStrategy, Effect, EvaluationMode, Kind, Matcher};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
-    pub parent: Addr,
+    pub owner: Addr,
     pub kind: Kind,
+    /// Decode base64 encoded json payloads (i.e.: the `msg` of a wasm execute)
+    /// and known stargate messages before matching. Defaults to false.
+    #[serde(default)]
+    pub decode_binaries: bool,
+    /// How to resolve matchers with different effects. Defaults to deny
+    /// overrides.
+    #[serde(default)]
+    pub conflict_strategy: ConflictStrategy,
+    /// How the messages are compared to the matchers. Defaults to a single
+    /// matcher for all the messages.
+    #[serde(default)]
+    pub evaluation_mode: EvaluationMode,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    AddAuthorization { addr: Addr, msg: String },
-    RemoveAuthorization { addr: Addr, msg: String },
+    /// Adds a matcher for addr. The effect defaults to the kind of the
+    /// contract and the priority to 0. Allow matchers can have a budget and a
+    /// maximum number of uses, after which they are removed.
+    AddAuthorization {
+        addr: Addr,
+        msg: String,
+        effect: Option<Effect>,
+        priority: Option<u32>,
+        budget: Option<Budget>,
+        max_uses: Option<u64>,
+    },
+    RemoveAuthorization {
+        addr: Addr,
+        msg: String,
+    },
+    /// Adds a matcher that applies to every sender. Its uses are shared by
+    /// all the senders.
+    AddGlobalAuthorization {
+        msg: String,
+        effect: Option<Effect>,
+        priority: Option<u32>,
+        budget: Option<Budget>,
+        max_uses: Option<u64>,
+    },
+    RemoveGlobalAuthorization {
+        msg: String,
+    },
+    SetConflictStrategy {
+        strategy: ConflictStrategy,
+    },
+    SetEvaluationMode {
+        mode: EvaluationMode,
+    },
+    /// Sets the contracts allowed to record executions on behalf of any sender
+    SetUpdaters {
+        updaters: Vec<String>,
+    },
 }
 
 impl CustomMsg for ExecuteMsg {}
@@ -23,7 +70,15 @@ impl CustomMsg for ExecuteMsg {}
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    GetAuthorizations { sender: Addr },
+    GetAuthorizations {
+        sender: Addr,
+    },
+    GetGlobalAuthorizations {},
+    /// What the sender can still spend with each of its matchers that has a
+    /// budget, including the global ones
+    GetBudgets {
+        sender: Addr,
+    },
 }
 impl CustomMsg for QueryMsg {}
 
@@ -32,6 +87,21 @@ pub struct AuthorizationsResponse {
     pub authorizations: Vec<Matcher>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BudgetStatus {
+    pub matcher_id: u64,
+    pub matcher: String,
+    pub remaining: Vec<Coin>,
+    /// When the spent amounts are reset. None if nothing has been spent in
+    /// the current period or the budget has no period.
+    pub resets_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BudgetsResponse {
+    pub budgets: Vec<BudgetStatus>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {}
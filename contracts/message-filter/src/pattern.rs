@@ -0,0 +1,333 @@
+use std::cmp::Ordering;
+
+use cosmwasm_std::Uint128;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::regex::Regex;
+use crate::utils::Value;
+
+/// The keys a serialized CosmosMsg can have at the top level
+const MSG_KINDS: [&str; 8] = [
+    "bank",
+    "custom",
+    "staking",
+    "distribution",
+    "stargate",
+    "ibc",
+    "wasm",
+    "gov",
+];
+
+/// A matcher parsed into a tree, so it doesn't need to be parsed again for
+/// every query. Objects are stored as lists of entries because maps can't be
+/// serialized into the contract storage.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    /// The empty object. Matches anything
+    Wildcard {},
+    Null {},
+    Bool(bool),
+    U128(Uint128),
+    Number(u64),
+    String(String),
+    Array(Vec<Pattern>),
+    Object(Vec<(String, Pattern)>),
+    /// An object whose keys are all operators. All of them must match
+    Operators(Vec<Operator>),
+}
+
+/// The operators that can be used in a matcher instead of a literal value. For
+/// example, `{"amount": {"$lte": "1000"}}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    /// `$lte`. Uint128 strings are compared numerically, other strings
+    /// lexicographically
+    Lte(Box<Pattern>),
+    /// `$gte`
+    Gte(Box<Pattern>),
+    /// `$in`. Any of the patterns matches
+    In(Vec<Pattern>),
+    /// `$not`
+    Not(Box<Pattern>),
+    /// `$prefix`
+    Prefix(String),
+    /// `$regex`. See the regex module for the supported syntax. Stored
+    /// compiled so that it isn't compiled again for every match
+    Regex(Regex),
+    /// `$any`. Some element of the array matches
+    Any(Box<Pattern>),
+    /// `$all`. Every element of the array matches
+    All(Box<Pattern>),
+    /// `$contains`. Each pattern matches a different element of the array
+    Contains(Vec<Pattern>),
+    /// `$unordered`. Like `$contains`, but with no elements left over
+    Unordered(Vec<Pattern>),
+}
+
+fn as_u128(value: &Value) -> Option<u128> {
+    match value {
+        Value::U128(n) => Some(n.u128()),
+        Value::Number(n) => Some(*n as u128),
+        _ => None,
+    }
+}
+
+fn as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::U128(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Numbers (including Uint128 strings) are compared numerically, other
+/// strings lexicographically. Anything else can't be compared.
+fn compare(msg: &Value, bound: &Pattern) -> Option<Ordering> {
+    match (msg, bound) {
+        (Value::String(msg), Pattern::String(bound)) => Some(msg.as_str().cmp(bound)),
+        (msg, Pattern::U128(bound)) => Some(as_u128(msg)?.cmp(&bound.u128())),
+        (msg, Pattern::Number(bound)) => Some(as_u128(msg)?.cmp(&(*bound as u128))),
+        _ => None,
+    }
+}
+
+fn parse_bound(op: &str, arg: &Value) -> Result<Box<Pattern>, String> {
+    match arg {
+        Value::U128(_) | Value::Number(_) | Value::String(_) => Ok(Box::new(Pattern::parse(arg)?)),
+        _ => Err(format!("{} expects a number or a string", op)),
+    }
+}
+
+fn parse_list(op: &str, arg: &Value) -> Result<Vec<Pattern>, String> {
+    match arg {
+        Value::Array(array) => array.iter().map(Pattern::parse).collect(),
+        _ => Err(format!("{} expects an array", op)),
+    }
+}
+
+/// Checks if every pattern can be matched by a different element. Elements are
+/// assigned by backtracking, so a greedy choice doesn't hide a valid match.
+fn match_distinct(elems: &[Value], patterns: &[Pattern], used: &mut [bool]) -> bool {
+    let (pattern, rest) = match patterns.split_first() {
+        Some(split) => split,
+        None => return true,
+    };
+    for (i, elem) in elems.iter().enumerate() {
+        if !used[i] && pattern.matches(elem) {
+            used[i] = true;
+            if match_distinct(elems, rest, used) {
+                return true;
+            }
+            used[i] = false;
+        }
+    }
+    false
+}
+
+impl Operator {
+    fn parse(op: &str, arg: &Value) -> Result<Operator, String> {
+        Ok(match op {
+            "$lte" => Operator::Lte(parse_bound(op, arg)?),
+            "$gte" => Operator::Gte(parse_bound(op, arg)?),
+            "$in" => Operator::In(parse_list(op, arg)?),
+            "$not" => Operator::Not(Box::new(Pattern::parse(arg)?)),
+            "$prefix" => match as_string(arg) {
+                Some(prefix) => Operator::Prefix(prefix),
+                None => return Err("$prefix expects a string".to_string()),
+            },
+            "$regex" => match arg {
+                Value::String(pattern) => Operator::Regex(Regex::new(pattern)?),
+                _ => return Err("$regex expects a string".to_string()),
+            },
+            "$any" => Operator::Any(Box::new(Pattern::parse(arg)?)),
+            "$all" => Operator::All(Box::new(Pattern::parse(arg)?)),
+            "$contains" => Operator::Contains(parse_list(op, arg)?),
+            "$unordered" => Operator::Unordered(parse_list(op, arg)?),
+            op => return Err(format!("Unknown operator {}", op)),
+        })
+    }
+
+    fn matches(&self, msg: &Value) -> bool {
+        match self {
+            Operator::Lte(bound) => {
+                matches!(compare(msg, bound), Some(Ordering::Less | Ordering::Equal))
+            }
+            Operator::Gte(bound) => matches!(
+                compare(msg, bound),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+            Operator::In(options) => options.iter().any(|o| o.matches(msg)),
+            Operator::Not(pattern) => !pattern.matches(msg),
+            Operator::Prefix(prefix) => as_string(msg).is_some_and(|s| s.starts_with(prefix)),
+            Operator::Regex(regex) => as_string(msg).is_some_and(|s| regex.is_match(&s)),
+            Operator::Any(pattern) => match msg {
+                Value::Array(elems) => elems.iter().any(|elem| pattern.matches(elem)),
+                _ => false,
+            },
+            Operator::All(pattern) => match msg {
+                Value::Array(elems) => elems.iter().all(|elem| pattern.matches(elem)),
+                _ => false,
+            },
+            Operator::Contains(patterns) => match msg {
+                Value::Array(elems) => {
+                    match_distinct(elems, patterns, &mut vec![false; elems.len()])
+                }
+                _ => false,
+            },
+            Operator::Unordered(patterns) => match msg {
+                Value::Array(elems) => {
+                    elems.len() == patterns.len()
+                        && match_distinct(elems, patterns, &mut vec![false; elems.len()])
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+fn check_msg_pattern(pattern: &Pattern) -> Result<(), String> {
+    match pattern {
+        Pattern::Wildcard {} | Pattern::Operators(_) => Ok(()),
+        Pattern::Object(entries) => {
+            if entries.len() > 1 {
+                return Err("A message only has one of the top level keys".to_string());
+            }
+            match entries
+                .iter()
+                .find(|(key, _)| !MSG_KINDS.contains(&key.as_str()))
+            {
+                Some((key, _)) => Err(format!("{} is not a kind of message", key)),
+                None => Ok(()),
+            }
+        }
+        _ => Err("Messages can only be matched by objects".to_string()),
+    }
+}
+
+impl Pattern {
+    /// Parses a json matcher, checking that all the operators used in it exist
+    /// and have valid arguments
+    pub fn parse(value: &Value) -> Result<Pattern, String> {
+        Ok(match value {
+            Value::Null => Pattern::Null {},
+            Value::Bool(b) => Pattern::Bool(*b),
+            Value::U128(n) => Pattern::U128(*n),
+            Value::Number(n) => Pattern::Number(*n),
+            Value::String(s) => Pattern::String(s.clone()),
+            Value::Array(array) => {
+                Pattern::Array(array.iter().map(Pattern::parse).collect::<Result<_, _>>()?)
+            }
+            Value::Object(map) if map.is_empty() => Pattern::Wildcard {},
+            Value::Object(map) if map.keys().all(|key| key.starts_with('$')) => Pattern::Operators(
+                map.iter()
+                    .map(|(op, arg)| Operator::parse(op, arg))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(map) => Pattern::Object(
+                map.iter()
+                    .map(|(key, val)| Ok((key.clone(), Pattern::parse(val)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+        })
+    }
+
+    /// Parses a matcher that will be compared to CosmosMsgs, rejecting the ones
+    /// that could never match a message. Arrays of message matchers are also
+    /// accepted, to match the whole batch of messages as a sequence.
+    pub fn parse_msg_matcher(value: &Value) -> Result<Pattern, String> {
+        let pattern = Pattern::parse(value)?;
+        match &pattern {
+            Pattern::Array(patterns) => patterns.iter().try_for_each(check_msg_pattern)?,
+            pattern => check_msg_pattern(pattern)?,
+        }
+        Ok(pattern)
+    }
+
+    pub fn matches(&self, msg: &Value) -> bool {
+        match self {
+            Pattern::Wildcard {} => true,
+            Pattern::Null {} => *msg == Value::Null,
+            Pattern::Bool(b) => *msg == Value::Bool(*b),
+            Pattern::U128(n) => *msg == Value::U128(*n),
+            Pattern::Number(n) => *msg == Value::Number(*n),
+            Pattern::String(s) => matches!(msg, Value::String(msg) if msg == s),
+            // Comparing arrays position by position, using the deep matching
+            Pattern::Array(patterns) => match msg {
+                Value::Array(elems) => {
+                    elems.len() == patterns.len()
+                        && patterns.iter().zip(elems).all(|(p, elem)| p.matches(elem))
+                }
+                _ => false,
+            },
+            Pattern::Object(entries) => match msg {
+                Value::Object(map) => entries
+                    .iter()
+                    .all(|(key, p)| map.get(key).is_some_and(|val| p.matches(val))),
+                _ => false,
+            },
+            Pattern::Operators(operators) => operators.iter().all(|op| op.matches(msg)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json_wasm::from_str;
+
+    fn parse(matcher: &str) -> Result<Pattern, String> {
+        Pattern::parse(&from_str(matcher).unwrap())
+    }
+
+    fn parse_msg_matcher(matcher: &str) -> Result<Pattern, String> {
+        Pattern::parse_msg_matcher(&from_str(matcher).unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(parse(r#"{"bank": {"send": {"to_address": {"$in": ["a", "b"]}}}}"#).is_ok());
+        assert!(parse(r#"{"bank": {"send": {"to_address": {"$not": {"$regex": "^a"}}}}}"#).is_ok());
+        assert!(parse(r#"{"bank": {"send": {"to_address": {"$eq": "a"}}}}"#).is_err());
+        assert!(parse(r#"{"bank": {"send": {"to_address": {"$in": "a"}}}}"#).is_err());
+        assert!(parse(r#"{"bank": {"send": {"to_address": {"$regex": "(a|b)"}}}}"#).is_err());
+        assert!(parse(r#"{"bank": {"send": {"amount": {"$contains": {}}}}}"#).is_err());
+        assert!(
+            parse(r#"{"bank": {"send": {"amount": {"$any": {"denom": {"$eq": "a"}}}}}}"#).is_err()
+        );
+        assert!(parse(r#"{"bank": {"send": {"amount": {"$lte": {}}}}}"#).is_err());
+
+        assert_eq!(
+            parse(r#"{"bank": {"send": {"amount": {"$lte": "10"}}}}"#).unwrap(),
+            Pattern::Object(vec![(
+                "bank".to_string(),
+                Pattern::Object(vec![(
+                    "send".to_string(),
+                    Pattern::Object(vec![(
+                        "amount".to_string(),
+                        Pattern::Operators(vec![Operator::Lte(Box::new(Pattern::U128(
+                            Uint128::new(10)
+                        )))])
+                    )])
+                )])
+            )])
+        );
+    }
+
+    #[test]
+    fn test_parse_msg_matcher() {
+        assert!(parse_msg_matcher(r#"{"bank": {}}"#).is_ok());
+        assert!(parse_msg_matcher(r#"{"custom": {"mint": {}}}"#).is_ok());
+        assert!(parse_msg_matcher(r#"{}"#).is_ok());
+        assert!(parse_msg_matcher(r#"{"$in": [{"bank": {}}, {"staking": {}}]}"#).is_ok());
+
+        assert!(parse_msg_matcher(r#"{"bank": {}, "staking": {}}"#).is_err());
+        assert!(parse_msg_matcher(r#"{"banks": {}}"#).is_err());
+        assert!(parse_msg_matcher(r#"[{"bank": {}}, {"staking": {}}]"#).is_ok());
+        assert!(parse_msg_matcher(r#"[{"bank": {}}, "staking"]"#).is_err());
+        assert!(parse_msg_matcher(r#""bank""#).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! A small regex engine used by the `$regex` matcher operator.
+//!
+//! Only a subset of the usual syntax is supported: literals, `.`, character
+//! classes (`[a-z0-9]`, `[^0-9]`), the escapes `\d`, `\w` and `\s`, escaped
+//! metacharacters, the quantifiers `*`, `+` and `?`, and the anchors `^` and
+//! `$`. Groups, alternations and counted repetitions are rejected when the
+//! pattern is compiled.
+//!
+//! Matching simulates the pattern as an NFA instead of backtracking, so it
+//! takes time linear in the length of the text whatever the pattern is.
+//!
+//! Compiled patterns are serializable so that matchers can be stored already
+//! compiled.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Characters are stored as code points because the contract's json
+/// serializer doesn't support chars
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum Atom {
+    Any {},
+    Char(u32),
+    Class {
+        ranges: Vec<(u32, u32)>,
+        negated: bool,
+    },
+}
+
+impl Atom {
+    fn matches(&self, c: char) -> bool {
+        let c = c as u32;
+        match self {
+            Atom::Any {} => true,
+            Atom::Char(expected) => c == *expected,
+            Atom::Class { ranges, negated } => {
+                ranges.iter().any(|(from, to)| *from <= c && c <= *to) != *negated
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum Quantifier {
+    One {},
+    ZeroOrOne {},
+    ZeroOrMore {},
+    OneOrMore {},
+}
+
+impl Quantifier {
+    fn bounds(&self) -> (usize, Option<usize>) {
+        match self {
+            Quantifier::One {} => (1, Some(1)),
+            Quantifier::ZeroOrOne {} => (0, Some(1)),
+            Quantifier::ZeroOrMore {} => (0, None),
+            Quantifier::OneOrMore {} => (1, None),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct Piece {
+    atom: Atom,
+    quantifier: Quantifier,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Regex {
+    pieces: Vec<Piece>,
+    start_anchor: bool,
+    end_anchor: bool,
+}
+
+fn code_points(ranges: Vec<(char, char)>) -> Vec<(u32, u32)> {
+    ranges
+        .into_iter()
+        .map(|(from, to)| (from as u32, to as u32))
+        .collect()
+}
+
+fn escape_ranges(c: char) -> Option<Vec<(char, char)>> {
+    match c {
+        'd' => Some(vec![('0', '9')]),
+        'w' => Some(vec![('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')]),
+        's' => Some(vec![(' ', ' '), ('\t', '\t'), ('\n', '\n'), ('\r', '\r')]),
+        _ => None,
+    }
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let mut chars = pattern.chars().peekable();
+        let start_anchor = chars.next_if_eq(&'^').is_some();
+        let mut end_anchor = false;
+        let mut pieces: Vec<Piece> = vec![];
+
+        while let Some(c) = chars.next() {
+            if end_anchor {
+                return Err("'$' is only supported at the end of the pattern".to_string());
+            }
+            let atom = match c {
+                '.' => Atom::Any {},
+                '$' => {
+                    end_anchor = true;
+                    continue;
+                }
+                '\\' => {
+                    let escaped = chars.next().ok_or("Trailing '\\' in pattern")?;
+                    match escape_ranges(escaped) {
+                        Some(ranges) => Atom::Class {
+                            ranges: code_points(ranges),
+                            negated: false,
+                        },
+                        None => Atom::Char(escaped as u32),
+                    }
+                }
+                '[' => {
+                    let negated = chars.next_if_eq(&'^').is_some();
+                    let mut ranges = vec![];
+                    loop {
+                        let from = match chars.next() {
+                            None => return Err("Unclosed '[' in pattern".to_string()),
+                            Some(']') => break,
+                            Some('\\') => {
+                                let escaped = chars.next().ok_or("Trailing '\\' in pattern")?;
+                                if let Some(escaped_ranges) = escape_ranges(escaped) {
+                                    ranges.extend(escaped_ranges);
+                                    continue;
+                                }
+                                escaped
+                            }
+                            Some(from) => from,
+                        };
+                        let to = match chars.peek() {
+                            Some('-') => {
+                                chars.next();
+                                match chars.next() {
+                                    Some(']') => {
+                                        // A trailing '-' is a literal
+                                        ranges.push((from, from));
+                                        ranges.push(('-', '-'));
+                                        break;
+                                    }
+                                    Some(to) if to >= from => to,
+                                    Some(_) => return Err("Invalid range in pattern".to_string()),
+                                    None => return Err("Unclosed '[' in pattern".to_string()),
+                                }
+                            }
+                            _ => from,
+                        };
+                        ranges.push((from, to));
+                    }
+                    Atom::Class {
+                        ranges: code_points(ranges),
+                        negated,
+                    }
+                }
+                '*' | '+' | '?' => {
+                    return Err(format!("Nothing to repeat before '{}'", c));
+                }
+                '(' | ')' | '|' | '{' | '}' | '^' => {
+                    return Err(format!("Unsupported '{}' in pattern", c));
+                }
+                c => Atom::Char(c as u32),
+            };
+
+            let quantifier = match chars.peek() {
+                Some('*') => Quantifier::ZeroOrMore {},
+                Some('+') => Quantifier::OneOrMore {},
+                Some('?') => Quantifier::ZeroOrOne {},
+                _ => Quantifier::One {},
+            };
+            if !matches!(quantifier, Quantifier::One {}) {
+                chars.next();
+            }
+            pieces.push(Piece { atom, quantifier });
+        }
+
+        Ok(Regex {
+            pieces,
+            start_anchor,
+            end_anchor,
+        })
+    }
+
+    /// Keeps the set of states the pattern can be in after each character.
+    /// State `entered[i]` is about to match piece `i` (`entered[len]` is the
+    /// end of the pattern) and `looping[i]` has matched a repeating piece `i`
+    /// at least once.
+    pub fn is_match(&self, text: &str) -> bool {
+        let len = self.pieces.len();
+        let mut entered = vec![false; len + 1];
+        let mut looping = vec![false; len];
+        entered[0] = true;
+        self.follow_skips(&mut entered, &looping);
+
+        for c in text.chars() {
+            if entered[len] && !self.end_anchor {
+                return true;
+            }
+            let mut next_entered = vec![false; len + 1];
+            let mut next_looping = vec![false; len];
+            for (i, piece) in self.pieces.iter().enumerate() {
+                if (entered[i] || looping[i]) && piece.atom.matches(c) {
+                    match piece.quantifier.bounds() {
+                        (_, None) => next_looping[i] = true,
+                        _ => next_entered[i + 1] = true,
+                    }
+                }
+            }
+            // Without a start anchor a match can begin at any character
+            next_entered[0] |= !self.start_anchor;
+            self.follow_skips(&mut next_entered, &next_looping);
+            entered = next_entered;
+            looping = next_looping;
+        }
+        entered[len]
+    }
+
+    /// Adds the states reachable without consuming a character: optional
+    /// pieces can be skipped and repeating pieces can stop repeating. These
+    /// only lead forward, so a single pass is enough.
+    fn follow_skips(&self, entered: &mut [bool], looping: &[bool]) {
+        for (i, piece) in self.pieces.iter().enumerate() {
+            let (min, _) = piece.quantifier.bounds();
+            if looping[i] || (entered[i] && min == 0) {
+                entered[i + 1] = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_regex() {
+        assert!(is_match("juno", "address_juno_1"));
+        assert!(!is_match("^juno", "address_juno_1"));
+        assert!(is_match("^juno1[a-z0-9]+$", "juno1abc123"));
+        assert!(!is_match("^juno1[a-z0-9]+$", "juno1ABC"));
+        assert!(!is_match("^juno1[a-z0-9]+$", "juno1"));
+        assert!(is_match(r"^\d+u?juno$", "100ujuno"));
+        assert!(is_match(r"^\d+u?juno$", "100juno"));
+        assert!(!is_match(r"^\d+u?juno$", "juno"));
+        assert!(is_match(r"^ibc/[^/]*$", "ibc/ABCDEF"));
+        assert!(!is_match(r"^ibc/[^/]*$", "ibc/AB/CD"));
+        assert!(is_match(r"^a\.b.c$", "a.bxc"));
+        assert!(!is_match(r"^a\.b.c$", "axbxc"));
+        assert!(is_match("^a*a*b$", "aaab"));
+        assert!(is_match("a+b?c", "xaaacz"));
+        assert!(!is_match("^a+b?c", "bc"));
+        assert!(is_match("", "anything"));
+        assert!(is_match("^$", ""));
+        assert!(!is_match("^$", "a"));
+
+        assert!(Regex::new("(a|b)").is_err());
+        assert!(Regex::new("a{2}").is_err());
+        assert!(Regex::new("*a").is_err());
+        assert!(Regex::new("[a-").is_err());
+        assert!(Regex::new("a$b").is_err());
+    }
+
+    #[test]
+    fn test_regex_is_linear() {
+        // This takes exponential time for a backtracking matcher
+        let pattern = "a*a*a*a*a*a*a*a*a*a*a*a*b";
+        let text = "a".repeat(10_000);
+        assert!(!is_match(pattern, &text));
+        assert!(is_match(pattern, &format!("{}b", text)));
+    }
+
+    #[test]
+    fn test_serialized_regex() {
+        let regex = Regex::new(r"^ibc/[^/a-]\d*.?$").unwrap();
+        let stored: Regex =
+            cosmwasm_std::from_slice(&cosmwasm_std::to_vec(&regex).unwrap()).unwrap();
+        assert_eq!(stored, regex);
+        assert!(stored.is_match("ibc/B12x"));
+        assert!(!stored.is_match("ibc/-12"));
+    }
+}
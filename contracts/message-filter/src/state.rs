@@ -1,36 +1,116 @@
-use cosmwasm_std::{Addr, Response};
+use cosmwasm_std::{Addr, Coin, Response, StdResult, Storage, Timestamp};
+use cw_authorizations::ownership::OwnershipState;
 use cw_authorizations::AuthorizationError;
 use cw_storage_plus::{Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::pattern::Pattern;
+use crate::MessageFilterError;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum Kind {
     Allow {},
     Reject {},
 }
 
+/// What happens to the messages matched by a matcher
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    Allow {},
+    Deny {},
+}
+
+/// How to decide when matchers with different effects match the same messages
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Any matching deny matcher rejects the messages
+    DenyOverrides {},
+    /// Any matching allow matcher authorizes the messages
+    AllowOverrides {},
+    /// The matching matcher with the highest priority decides. Ties go to the
+    /// matcher that was added first, with the sender's matchers before the
+    /// global ones
+    FirstMatch {},
+}
+
+impl Default for ConflictStrategy {
+    fn default() -> Self {
+        ConflictStrategy::DenyOverrides {}
+    }
+}
+
+/// How the messages of a request are compared to the matchers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationMode {
+    /// A single matcher must match all the messages. Deny matchers reject
+    /// the request if they match any of the messages
+    SingleMatcher {},
+    /// Each message is checked on its own and must be authorized by some
+    /// matcher
+    PerMessage {},
+    /// Array matchers are compared to the list of messages as a whole, so
+    /// [{"bank": {}}, {"staking": {}}] matches a bank message followed by a
+    /// staking one. Other matchers are checked as in `SingleMatcher`
+    Sequence {},
+}
+
+impl Default for EvaluationMode {
+    fn default() -> Self {
+        EvaluationMode::SingleMatcher {}
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
 pub struct Config {
-    /// The address of the owner that this authorization module is associated
-    /// with. This is usually another contract, which is why the name parent is
-    /// used
-    pub parent: Addr,
     /// The type of authorization this is. Kind::Allow means messages will only
     /// be authorized (allowed) if there is a matching Authorization in the
     /// contract. Kind::Reject means all messages will be authorized (allowed)
     /// by this contract unless explicitly rejected by one of the stored
     /// authorizations
     pub kind: Kind,
+    /// If set, base64 encoded json payloads in the messages (for example, the
+    /// `msg` of a `WasmMsg::Execute`) are decoded before matching, so
+    /// matchers can inspect the contract calls. Nested payloads are decoded
+    /// too. The protobuf values of stargate messages with a known type url
+    /// are decoded as well.
+    #[serde(default)]
+    pub decode_binaries: bool,
+    /// How to resolve matchers with different effects. Contracts where all
+    /// the matchers have the effect of their kind behave the same with any
+    /// strategy.
+    #[serde(default)]
+    pub conflict_strategy: ConflictStrategy,
+    #[serde(default)]
+    pub evaluation_mode: EvaluationMode,
+    /// Contracts allowed to record executions on behalf of any sender, like the
+    /// executor or a parent composite. Senders can always record their own.
+    #[serde(default)]
+    pub updaters: Vec<Addr>,
 }
 
 impl Config {
+    /// The effect of a matcher. Matchers without one have the effect implied
+    /// by the kind of the contract.
+    pub fn effect(&self, matcher: &Matcher) -> Effect {
+        matcher.effect.unwrap_or(self.effect_of_kind())
+    }
+
+    pub fn effect_of_kind(&self) -> Effect {
+        match self.kind {
+            Kind::Allow {} => Effect::Allow {},
+            Kind::Reject {} => Effect::Deny {},
+        }
+    }
+
     pub fn default_response(&self) -> Result<Response, AuthorizationError> {
         match self.kind {
             Kind::Allow {} => Err(AuthorizationError::Unauthorized {
-                //reason: Some("No authorizations allowed the request. Rejecting.".to_string()),
-            }
-            .into()),
+                reason: Some("No authorizations allowed the request. Rejecting.".to_string()),
+            }),
             Kind::Reject {} => Ok(Response::default()
                 .add_attribute("allowed", "true")
                 .add_attribute(
@@ -40,10 +120,12 @@ impl Config {
         }
     }
 
-    pub fn default_authorization(&self) -> bool {
+    pub fn default_authorization(&self) -> Result<bool, AuthorizationError<MessageFilterError>> {
         match self.kind {
-            Kind::Allow {} => false,
-            Kind::Reject {} => true,
+            Kind::Allow {} => Err(AuthorizationError::ContractError(
+                MessageFilterError::NoMatchingAuthorization {},
+            )),
+            Kind::Reject {} => Ok(true),
         }
     }
 }
@@ -65,14 +147,73 @@ pub struct Matcher {
     /// However, {"bank": {}} will match all bank messages, and
     /// {"bank": {"send": {"to_address": "an_address", "amount": {}}}} will match all bank messages to "an_address".
     ///
+    /// Values can also be replaced by operator objects: `$lte` and `$gte`
+    /// (numeric for Uint128 strings), `$in` (any of a list of matchers),
+    /// `$not`, `$prefix` and `$regex`. For example,
+    ///
+    /// {"bank": {"send": {"to_address": {"$in": ["a", "b"]}, "amount": [{"denom": "ujuno", "amount": {"$lte": "1000"}}]}}}
+    ///
+    /// will match sends of at most 1000 ujuno to "a" or "b".
+    ///
+    /// Arrays are compared position by position. To match them differently,
+    /// use `$any` (some element matches), `$all` (every element matches),
+    /// `$contains` (each matcher in the list matches a different element) or
+    /// `$unordered` (like `$contains`, but with no elements left over).
+    ///
     pub matcher: String,
-    /// The address of this matcher is applicable to.
-    pub addr: Addr,
+    /// The address of this matcher is applicable to. Global matchers apply to
+    /// every sender and have no address.
+    pub addr: Option<Addr>,
+    /// The matcher parsed when it was added. This is what messages are
+    /// compared to.
+    pub pattern: Pattern,
+    /// Whether the matched messages are allowed or denied. Defaults to the
+    /// kind of the contract.
+    pub effect: Option<Effect>,
+    /// Matchers with a higher priority are evaluated first
+    #[serde(default)]
+    pub priority: u32,
+    /// Identifies the matcher in the spends
+    #[serde(default)]
+    pub id: u64,
+    /// Limits the total amount allow matchers can authorize for each sender
+    #[serde(default)]
+    pub budget: Option<Budget>,
+    /// How many more times an allow matcher can authorize messages. It is
+    /// removed when none are left.
+    #[serde(default)]
+    pub max_uses: Option<u64>,
+}
+
+/// A limit on the total amount the messages authorized by a matcher can spend.
+/// Messages whose spend can't be worked out (custom messages and stargate
+/// messages of unknown types) are never authorized by a matcher with a budget.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Budget {
+    /// The maximum amount of each denom. Denoms that aren't listed can't be
+    /// spent.
+    pub limits: Vec<Coin>,
+    /// How long a period lasts, in seconds. The spent amounts are reset when
+    /// it ends. Budgets without a period are never reset.
+    pub period: Option<u64>,
+}
+
+/// What a sender has spent with a matcher in the current period
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Spend {
+    pub period_start: Timestamp,
+    pub amounts: Vec<Coin>,
 }
 
 pub struct MessageFilterState {
     pub config: Item<'static, Config>,
     pub matchers: Map<'static, Addr, Vec<Matcher>>,
+    /// Matchers that apply to every sender
+    pub global_matchers: Item<'static, Vec<Matcher>>,
+    pub next_matcher_id: Item<'static, u64>,
+    /// What each sender has spent with each matcher, by (matcher id, sender)
+    pub spends: Map<'static, (u64, Addr), Spend>,
+    pub ownership: OwnershipState,
 }
 
 impl MessageFilterState {
@@ -80,6 +221,58 @@ impl MessageFilterState {
         MessageFilterState {
             config: Item::new("config"),
             matchers: Map::new("matchers"),
+            global_matchers: Item::new("global_matchers"),
+            next_matcher_id: Item::new("next_matcher_id"),
+            spends: Map::new("spends"),
+            ownership: OwnershipState::new(),
+        }
+    }
+
+    pub fn next_matcher_id(&self, storage: &mut dyn Storage) -> StdResult<u64> {
+        let id = self.next_matcher_id.may_load(storage)?.unwrap_or(1);
+        self.next_matcher_id.save(storage, &(id + 1))?;
+        Ok(id)
+    }
+
+    /// Loads the matchers of an address, or the global ones if there's no
+    /// address
+    pub fn may_load_matchers(
+        &self,
+        storage: &dyn Storage,
+        addr: Option<&Addr>,
+    ) -> StdResult<Option<Vec<Matcher>>> {
+        match addr {
+            Some(addr) => self.matchers.may_load(storage, addr.clone()),
+            None => self.global_matchers.may_load(storage),
+        }
+    }
+
+    pub fn save_matchers(
+        &self,
+        storage: &mut dyn Storage,
+        addr: Option<&Addr>,
+        matchers: &Vec<Matcher>,
+    ) -> StdResult<()> {
+        match addr {
+            Some(addr) => self.matchers.save(storage, addr.clone(), matchers),
+            None => self.global_matchers.save(storage, matchers),
         }
     }
+
+    /// The matchers that apply to a sender: its own ones followed by the
+    /// global ones
+    pub fn sender_matchers(&self, storage: &dyn Storage, sender: &Addr) -> StdResult<Vec<Matcher>> {
+        let mut matchers = self
+            .matchers
+            .may_load(storage, sender.clone())?
+            .unwrap_or_default();
+        matchers.extend(self.global_matchers.may_load(storage)?.unwrap_or_default());
+        Ok(matchers)
+    }
+}
+
+impl Default for MessageFilterState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -1,13 +1,25 @@
 #![cfg(test)]
-use cosmwasm_std::{coin, coins, Addr, BankMsg, CosmosMsg, Empty, StakingMsg};
+use cosmwasm_std::{
+    coin, coins, to_binary, Addr, BankMsg, Binary, CosmosMsg, CustomMsg, Deps, DepsMut, Empty, Env,
+    MessageInfo, Response, StakingMsg, StdError, StdResult, Uint128, WasmMsg,
+};
 use cw_authorizations::msg::{
-    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, IsAuthorizedResponse,
+    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, InfoResponse, IsAuthorizedPerMessageResponse,
+    IsAuthorizedResponse,
 };
+use cw_authorizations::ownership::Ownership;
+use cw_authorizations::{Authorization, AuthorizationError};
 use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    msg::{ExecuteMsg, InstantiateMsg, QueryMsg},
+    contract::MessageFilterContract,
+    msg::{AuthorizationsResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg},
+    pattern::Pattern,
     state::Kind,
+    MessageFilterError,
 };
 
 fn contract() -> Box<dyn Contract<Empty>> {
@@ -15,7 +27,8 @@ fn contract() -> Box<dyn Contract<Empty>> {
         crate::contract::execute,
         crate::contract::instantiate,
         crate::contract::query,
-    );
+    )
+    .with_migrate(crate::contract::migrate);
     Box::new(contract)
 }
 
@@ -28,8 +41,11 @@ fn test_simple_filtering() {
     // Create a message-filter contract
     let code_id = app.store_code(contract());
     let instantiate_msg = InstantiateMsg {
-        parent: Addr::unchecked(CREATOR),
+        owner: Addr::unchecked(CREATOR),
         kind: Kind::Allow {},
+        decode_binaries: false,
+        conflict_strategy: Default::default(),
+        evaluation_mode: Default::default(),
     };
     let contract_addr = app
         .instantiate_contract(
@@ -53,20 +69,24 @@ fn test_simple_filtering() {
     app.execute_contract(
         Addr::unchecked(CREATOR),
         contract_addr.clone(),
-        &AuthoriazationExecuteMsg::Extension(ExecuteMsg::AddAuthorization {
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
             addr: Addr::unchecked("Someone"),
             msg: r#"{"bank": {}}"#.to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
         }),
         &[],
     )
     .unwrap();
 
-    let IsAuthorizedResponse { authorized } = app
+    let IsAuthorizedResponse { authorized, .. } = app
         .wrap()
         .query_wasm_smart(
             contract_addr.clone(),
             &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
-                sender: Addr::unchecked("Someone"),
+                context: Addr::unchecked("Someone").into(),
                 msgs: msgs.clone(),
             },
         )
@@ -74,12 +94,12 @@ fn test_simple_filtering() {
     assert!(authorized);
 
     // No authorizations for sender
-    let IsAuthorizedResponse { authorized } = app
+    let IsAuthorizedResponse { authorized, .. } = app
         .wrap()
         .query_wasm_smart(
             contract_addr.clone(),
             &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
-                sender: Addr::unchecked("Someone_else"),
+                context: Addr::unchecked("Someone_else").into(),
                 msgs,
             },
         )
@@ -92,15 +112,1648 @@ fn test_simple_filtering() {
     }
     .into()];
 
-    let IsAuthorizedResponse { authorized } = app
+    let IsAuthorizedResponse { authorized, .. } = app
         .wrap()
         .query_wasm_smart(
             contract_addr.clone(),
             &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
-                sender: Addr::unchecked("Someone"),
+                context: Addr::unchecked("Someone").into(),
                 msgs: msgs.clone(),
             },
         )
         .unwrap();
     assert!(!authorized);
 }
+
+fn instantiate_filter(app: &mut App, kind: Kind) -> Addr {
+    instantiate_filter_with(app, kind, false)
+}
+
+fn instantiate_filter_with(app: &mut App, kind: Kind, decode_binaries: bool) -> Addr {
+    let code_id = app.store_code(contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(CREATOR),
+        &InstantiateMsg {
+            owner: Addr::unchecked(CREATOR),
+            kind,
+            decode_binaries,
+            conflict_strategy: Default::default(),
+            evaluation_mode: Default::default(),
+        },
+        &[],
+        "Message Filter",
+        None,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_denial_reasons() {
+    let mut app = App::default();
+    let allow_addr = instantiate_filter(&mut app, Kind::Allow {});
+    let reject_addr = instantiate_filter(&mut app, Kind::Reject {});
+
+    let msgs: Vec<CosmosMsg> = vec![BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1015, "earth"),
+    }
+    .into()];
+
+    for addr in [&allow_addr, &reject_addr] {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked("Someone"),
+                msg: r#"{"bank": {}}"#.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: None,
+            }),
+            &[],
+        )
+        .unwrap();
+    }
+
+    // Nothing allows the sender
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query_wasm_smart(
+            allow_addr,
+            &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                context: Addr::unchecked("Someone_else").into(),
+                msgs: msgs.clone(),
+            },
+        )
+        .unwrap();
+    assert!(!response.authorized);
+    assert_eq!(response.code.unwrap(), "no_matching_authorization");
+    assert!(response.reason.is_some());
+
+    // A matcher explicitly rejects the messages
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query_wasm_smart(
+            reject_addr.clone(),
+            &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                context: Addr::unchecked("Someone").into(),
+                msgs,
+            },
+        )
+        .unwrap();
+    assert!(!response.authorized);
+    assert_eq!(response.code.unwrap(), "rejected_by_matcher");
+    assert!(response.reason.unwrap().contains(r#"{"bank": {}}"#));
+
+    // Authorized responses don't carry a reason
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query_wasm_smart(
+            reject_addr,
+            &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                context: Addr::unchecked("Someone_else").into(),
+                msgs: vec![],
+            },
+        )
+        .unwrap();
+    assert_eq!(response, IsAuthorizedResponse::authorized());
+}
+
+#[test]
+fn test_per_message_verdicts() {
+    let mut app = App::default();
+    let addr = instantiate_filter(&mut app, Kind::Allow {});
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: r#"{"bank": {}}"#.to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+
+    let msgs: Vec<CosmosMsg> = vec![
+        BankMsg::Send {
+            to_address: "you".to_string(),
+            amount: coins(1015, "earth"),
+        }
+        .into(),
+        StakingMsg::Delegate {
+            validator: "validator".to_string(),
+            amount: coin(1, "earth"),
+        }
+        .into(),
+    ];
+
+    // The messages are rejected as a whole...
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query_wasm_smart(
+            addr.clone(),
+            &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                context: Addr::unchecked("Someone").into(),
+                msgs: msgs.clone(),
+            },
+        )
+        .unwrap();
+    assert!(!response.authorized);
+
+    // ...and the staking message is the one that caused it
+    let response: IsAuthorizedPerMessageResponse = app
+        .wrap()
+        .query_wasm_smart(
+            addr,
+            &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorizedPerMessage {
+                context: Addr::unchecked("Someone").into(),
+                msgs,
+            },
+        )
+        .unwrap();
+    assert_eq!(response.responses.len(), 2);
+    assert!(response.responses[0].authorized);
+    assert!(!response.responses[1].authorized);
+    assert_eq!(
+        response.responses[1].code,
+        Some("no_matching_authorization".to_string())
+    );
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum TokenFactoryMsg {
+    Mint { denom: String, amount: Uint128 },
+    Burn { denom: String, amount: Uint128 },
+}
+
+impl CustomMsg for TokenFactoryMsg {}
+
+/// The execute and query entry points of a message filter deployed on a chain
+/// with token factory messages, as a crate using message-filter as a library
+/// would define them
+fn execute_token_factory(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: AuthoriazationExecuteMsg<ExecuteMsg, TokenFactoryMsg>,
+) -> Result<Response, AuthorizationError<MessageFilterError>> {
+    MessageFilterContract::<TokenFactoryMsg>::new().execute(deps, env, info, msg)
+}
+
+fn query_token_factory(
+    deps: Deps,
+    env: Env,
+    msg: AuthoriazationQueryMsg<QueryMsg, TokenFactoryMsg>,
+) -> StdResult<Binary> {
+    MessageFilterContract::<TokenFactoryMsg>::new().query(deps, env, msg)
+}
+
+#[test]
+fn test_custom_messages() {
+    let mut app = App::default();
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        execute_token_factory,
+        crate::contract::instantiate,
+        query_token_factory,
+    )));
+    let addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(CREATOR),
+            &InstantiateMsg {
+                owner: Addr::unchecked(CREATOR),
+                kind: Kind::Allow {},
+                decode_binaries: false,
+                conflict_strategy: Default::default(),
+                evaluation_mode: Default::default(),
+            },
+            &[],
+            "Message Filter",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg, TokenFactoryMsg>::Extension(
+            ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked("Someone"),
+                msg: r#"{"custom": {"mint": {"denom": "factory/ujuno"}}}"#.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: None,
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+
+    let is_authorized = |msg: TokenFactoryMsg| -> IsAuthorizedResponse {
+        app.wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg, TokenFactoryMsg>::IsAuthorized {
+                    msgs: vec![CosmosMsg::Custom(msg)],
+                    context: Addr::unchecked("Someone").into(),
+                },
+            )
+            .unwrap()
+    };
+    assert!(
+        is_authorized(TokenFactoryMsg::Mint {
+            denom: "factory/ujuno".to_string(),
+            amount: Uint128::new(100),
+        })
+        .authorized
+    );
+    let response = is_authorized(TokenFactoryMsg::Burn {
+        denom: "factory/ujuno".to_string(),
+        amount: Uint128::new(100),
+    });
+    assert!(!response.authorized);
+    assert_eq!(response.code.unwrap(), "no_matching_authorization");
+}
+
+/// The config as it was stored before 0.2.0
+#[derive(Serialize, Deserialize)]
+struct ConfigV0_1 {
+    parent: Addr,
+    kind: Kind,
+}
+
+/// A matcher as it was stored before 0.2.0
+#[derive(Serialize, Deserialize)]
+struct MatcherV0_1 {
+    matcher: String,
+    addr: Addr,
+}
+
+fn instantiate_legacy(
+    deps: DepsMut,
+    name: &str,
+    version: &str,
+    msg: InstantiateMsg,
+) -> Result<Response, StdError> {
+    cw2::set_contract_version(deps.storage, name, version)?;
+    Item::new("config").save(
+        deps.storage,
+        &ConfigV0_1 {
+            parent: msg.owner,
+            kind: msg.kind,
+        },
+    )?;
+    let someone = Addr::unchecked("Someone");
+    cw_storage_plus::Map::new("matchers").save(
+        deps.storage,
+        someone.clone(),
+        &vec![
+            MatcherV0_1 {
+                matcher: r#"{"bank": {"send": {"to_address": "you", "amount": {}}}}"#.to_string(),
+                addr: someone.clone(),
+            },
+            MatcherV0_1 {
+                matcher: r#"{"staking": {}}"#.to_string(),
+                addr: someone,
+            },
+        ],
+    )?;
+    Ok(Response::default())
+}
+
+fn contract_v0_1() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        |deps: DepsMut, _env: Env, _info: MessageInfo, msg: InstantiateMsg| {
+            instantiate_legacy(deps, "crates.io:message-filter", "0.1.0", msg)
+        },
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn contract_from_the_future() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        |deps: DepsMut, _env: Env, _info: MessageInfo, msg: InstantiateMsg| {
+            instantiate_legacy(deps, "crates.io:message-filter", "99.0.0", msg)
+        },
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn other_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        |deps: DepsMut, _env: Env, _info: MessageInfo, msg: InstantiateMsg| {
+            instantiate_legacy(deps, "crates.io:whitelist", "0.1.0", msg)
+        },
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn instantiate_with(app: &mut App, contract: Box<dyn Contract<Empty>>) -> Addr {
+    let code_id = app.store_code(contract);
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(CREATOR),
+        &InstantiateMsg {
+            owner: Addr::unchecked(CREATOR),
+            kind: Kind::Allow {},
+            decode_binaries: false,
+            conflict_strategy: Default::default(),
+            evaluation_mode: Default::default(),
+        },
+        &[],
+        "Message Filter",
+        Some(CREATOR.to_string()),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_migrate_from_v0_1() {
+    let mut app = App::default();
+    let addr = instantiate_with(&mut app, contract_v0_1());
+    let code_id = app.store_code(contract());
+
+    let response = app
+        .migrate_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &MigrateMsg {},
+            code_id,
+        )
+        .unwrap();
+    assert!(response
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .any(|a| a.key == "from_version" && a.value == "0.1.0"));
+
+    // The parent became the owner
+    let ownership: Ownership = app
+        .wrap()
+        .query_wasm_smart(
+            addr.clone(),
+            &AuthoriazationQueryMsg::<QueryMsg>::Ownership {},
+        )
+        .unwrap();
+    assert_eq!(ownership.owner, Some(Addr::unchecked(CREATOR)));
+    let info: InfoResponse = app
+        .wrap()
+        .query_wasm_smart(addr.clone(), &AuthoriazationQueryMsg::<QueryMsg>::Info {})
+        .unwrap();
+    assert_eq!(info.contract_version, env!("CARGO_PKG_VERSION"));
+
+    // The stored matchers were parsed and given their own ids
+    let get_authorizations = |app: &App| -> AuthorizationsResponse {
+        app.wrap()
+            .query_wasm_smart(
+                addr.clone(),
+                &AuthoriazationQueryMsg::<QueryMsg>::Extension(QueryMsg::GetAuthorizations {
+                    sender: Addr::unchecked("Someone"),
+                }),
+            )
+            .unwrap()
+    };
+    let migrated = get_authorizations(&app).authorizations;
+    assert_eq!(migrated.len(), 2);
+    assert!(migrated
+        .iter()
+        .all(|m| matches!(m.pattern, Pattern::Object(_)) && m.id != 0));
+    assert_ne!(migrated[0].id, migrated[1].id);
+
+    let is_authorized = |app: &App, to_address: &str| -> bool {
+        app.wrap()
+            .query_wasm_smart::<IsAuthorizedResponse>(
+                addr.clone(),
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs: vec![BankMsg::Send {
+                        to_address: to_address.to_string(),
+                        amount: vec![coin(1, "uatom"), coin(2, "ujuno")],
+                    }
+                    .into()],
+                },
+            )
+            .unwrap()
+            .authorized
+    };
+    assert!(is_authorized(&app, "you"));
+    assert!(!is_authorized(&app, "someone else"));
+
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: r#"{"bank": {}}"#.to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+
+    // New matchers don't reuse the ids of the migrated ones
+    let authorizations = get_authorizations(&app).authorizations;
+    assert_eq!(authorizations.len(), 3);
+    assert!(migrated.iter().all(|m| m.id != authorizations[2].id));
+
+    // Migrating to the same version is a no-op
+    app.migrate_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &MigrateMsg {},
+        code_id,
+    )
+    .unwrap();
+    assert_eq!(get_authorizations(&app).authorizations, authorizations);
+}
+
+#[test]
+fn test_migrate_rejects_downgrades_and_other_contracts() {
+    let mut app = App::default();
+    let code_id = app.store_code(contract());
+
+    let addr = instantiate_with(&mut app, contract_from_the_future());
+    let err = app
+        .migrate_contract(Addr::unchecked(CREATOR), addr, &MigrateMsg {}, code_id)
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("Cannot downgrade"));
+
+    let other_addr = instantiate_with(&mut app, other_contract());
+    let err = app
+        .migrate_contract(
+            Addr::unchecked(CREATOR),
+            other_addr,
+            &MigrateMsg {},
+            code_id,
+        )
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("Cannot migrate from"));
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Cw20ExecuteMsg {
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+}
+
+fn cw20_call(msg: &Cw20ExecuteMsg) -> CosmosMsg {
+    WasmMsg::Execute {
+        contract_addr: "cw20".to_string(),
+        msg: to_binary(msg).unwrap(),
+        funds: vec![],
+    }
+    .into()
+}
+
+#[test]
+fn test_decode_binaries() {
+    let mut app = App::default();
+    let decoding = instantiate_filter_with(&mut app, Kind::Allow {}, true);
+    let opaque = instantiate_filter(&mut app, Kind::Allow {});
+
+    let matcher = r#"{"wasm": {"execute": {"contract_addr": "cw20", "msg": {"transfer": {"recipient": "x"}}}}}"#;
+    for addr in [&decoding, &opaque] {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked("Someone"),
+                msg: matcher.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: None,
+            }),
+            &[],
+        )
+        .unwrap();
+    }
+
+    let transfer = cw20_call(&Cw20ExecuteMsg::Transfer {
+        recipient: "x".to_string(),
+        amount: Uint128::new(10),
+    });
+    let send = cw20_call(&Cw20ExecuteMsg::Send {
+        contract: "x".to_string(),
+        amount: Uint128::new(10),
+        msg: to_binary(&Empty {}).unwrap(),
+    });
+    let is_authorized = |addr: &Addr, msg: &CosmosMsg| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs: vec![msg.clone()],
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+
+    assert!(is_authorized(&decoding, &transfer));
+    assert!(!is_authorized(&decoding, &send));
+    // Without decoding the payload can't be matched
+    assert!(!is_authorized(&opaque, &transfer));
+}
+
+#[test]
+fn test_decode_stargate() {
+    use crate::decoders::proto::{Coin, MsgSend};
+    use prost::Message;
+
+    let mut app = App::default();
+    let addr = instantiate_filter_with(&mut app, Kind::Allow {}, true);
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: r#"{"stargate": {"type_url": "/cosmos.bank.v1beta1.MsgSend", "value": {"to_address": "x"}}}"#
+                .to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+
+    let send_to = |to_address: &str| -> CosmosMsg {
+        CosmosMsg::Stargate {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+            value: Binary::from(
+                MsgSend {
+                    from_address: "Someone".to_string(),
+                    to_address: to_address.to_string(),
+                    amount: vec![Coin {
+                        denom: "juno".to_string(),
+                        amount: "100".to_string(),
+                    }],
+                }
+                .encode_to_vec(),
+            ),
+        }
+    };
+    let is_authorized = |msg: CosmosMsg| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs: vec![msg],
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+
+    assert!(is_authorized(send_to("x")));
+    assert!(!is_authorized(send_to("y")));
+}
+
+#[test]
+fn test_operator_matchers() {
+    let mut app = App::default();
+    let addr = instantiate_filter(&mut app, Kind::Allow {});
+
+    // Send at most 1000 ujuno to any of three addresses
+    let matcher = r#"{"bank": {"send": {"to_address": {"$in": ["a", "b", "c"]}, "amount": [{"denom": "ujuno", "amount": {"$lte": "1000"}}]}}}"#;
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: matcher.to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+
+    let is_authorized = |to_address: &str, amount: u128| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs: vec![BankMsg::Send {
+                        to_address: to_address.to_string(),
+                        amount: coins(amount, "ujuno"),
+                    }
+                    .into()],
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+    assert!(is_authorized("b", 1000));
+    assert!(is_authorized("c", 15));
+    assert!(!is_authorized("b", 1001));
+    assert!(!is_authorized("d", 15));
+
+    // Regexes are stored compiled
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: r#"{"bank": {"send": {"to_address": {"$regex": "^juno1[a-z0-9]+$"}}}}"#
+                .to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+    // A pattern that backtracking matchers take exponential time on
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: r#"{"bank": {"send": {"to_address": {"$regex": "^a*a*a*a*a*a*a*a*b$"}}}}"#
+                .to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+    let is_authorized = |to_address: &str| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs: vec![BankMsg::Send {
+                        to_address: to_address.to_string(),
+                        amount: coins(5000, "ujuno"),
+                    }
+                    .into()],
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+    assert!(is_authorized("juno1abc123"));
+    assert!(!is_authorized("juno1ABC"));
+    assert!(is_authorized("aaab"));
+    assert!(!is_authorized(&"a".repeat(1000)));
+
+    // Invalid operators are rejected when the matcher is added
+    let err = app
+        .execute_contract(
+            Addr::unchecked(CREATOR),
+            addr,
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked("Someone"),
+                msg: r#"{"bank": {"send": {"to_address": {"$regex": "(a|b)"}}}}"#.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: None,
+            }),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("Invalid matcher"));
+}
+
+#[test]
+fn test_array_operators() {
+    let mut app = App::default();
+    let addr = instantiate_filter(&mut app, Kind::Allow {});
+
+    // Each sender gets a matcher that uses a different operator on the coins
+    let matchers = [
+        (
+            "any",
+            r#"{"bank": {"send": {"amount": {"$any": {"denom": "ujuno"}}}}}"#,
+        ),
+        (
+            "all",
+            r#"{"bank": {"send": {"amount": {"$all": {"amount": {"$lte": "100"}}}}}}"#,
+        ),
+        (
+            "contains",
+            r#"{"bank": {"send": {"amount": {"$contains": [{"denom": "ujuno"}, {"denom": "uatom"}]}}}}"#,
+        ),
+        (
+            "unordered",
+            r#"{"bank": {"send": {"amount": {"$unordered": [{"denom": "ujuno"}, {"denom": "uatom"}]}}}}"#,
+        ),
+    ];
+    for (sender, matcher) in matchers {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked(sender),
+                msg: matcher.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: None,
+            }),
+            &[],
+        )
+        .unwrap();
+    }
+
+    let is_authorized = |sender: &str, amount: &[(u128, &str)]| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked(sender).into(),
+                    msgs: vec![BankMsg::Send {
+                        to_address: "you".to_string(),
+                        amount: amount.iter().map(|(a, d)| coin(*a, *d)).collect(),
+                    }
+                    .into()],
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+
+    assert!(is_authorized("any", &[(500, "uosmo"), (1, "ujuno")]));
+    assert!(!is_authorized("any", &[(500, "uosmo"), (1, "uatom")]));
+
+    assert!(is_authorized("all", &[(100, "uosmo"), (1, "ujuno")]));
+    assert!(!is_authorized("all", &[(100, "uosmo"), (101, "ujuno")]));
+
+    assert!(is_authorized(
+        "contains",
+        &[(1, "uatom"), (1, "ujuno"), (1, "uosmo")]
+    ));
+    assert!(!is_authorized("contains", &[(1, "ujuno"), (1, "uosmo")]));
+
+    assert!(is_authorized("unordered", &[(1, "uatom"), (1, "ujuno")]));
+    assert!(!is_authorized(
+        "unordered",
+        &[(1, "uatom"), (1, "ujuno"), (1, "uosmo")]
+    ));
+    assert!(!is_authorized("unordered", &[(1, "ujuno")]));
+}
+
+#[test]
+fn test_multi_send_operators() {
+    use crate::decoders::proto::{Coin, Input, MsgMultiSend, Output};
+    use prost::Message;
+
+    let mut app = App::default();
+    let addr = instantiate_filter_with(&mut app, Kind::Allow {}, true);
+
+    // Someone can only spend its own funds, and must pay the treasury
+    let matcher = r#"{"stargate": {"type_url": "/cosmos.bank.v1beta1.MsgMultiSend", "value": {"inputs": {"$all": {"address": "Someone"}}, "outputs": {"$any": {"address": "treasury"}}}}}"#;
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: matcher.to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+
+    let coins = || {
+        vec![Coin {
+            denom: "ujuno".to_string(),
+            amount: "100".to_string(),
+        }]
+    };
+    let multi_send = |inputs: &[&str], outputs: &[&str]| -> CosmosMsg {
+        CosmosMsg::Stargate {
+            type_url: "/cosmos.bank.v1beta1.MsgMultiSend".to_string(),
+            value: Binary::from(
+                MsgMultiSend {
+                    inputs: inputs
+                        .iter()
+                        .map(|address| Input {
+                            address: address.to_string(),
+                            coins: coins(),
+                        })
+                        .collect(),
+                    outputs: outputs
+                        .iter()
+                        .map(|address| Output {
+                            address: address.to_string(),
+                            coins: coins(),
+                        })
+                        .collect(),
+                }
+                .encode_to_vec(),
+            ),
+        }
+    };
+    let is_authorized = |msg: CosmosMsg| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs: vec![msg],
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+
+    assert!(is_authorized(multi_send(
+        &["Someone", "Someone"],
+        &["you", "treasury"]
+    )));
+    assert!(!is_authorized(multi_send(
+        &["Someone", "Someone else"],
+        &["you", "treasury"]
+    )));
+    assert!(!is_authorized(multi_send(&["Someone"], &["you", "me"])));
+}
+
+#[test]
+fn test_global_matchers() {
+    let mut app = App::default();
+    let deny_list = instantiate_filter(&mut app, Kind::Reject {});
+
+    // Nobody may ever migrate the treasury
+    let matcher = r#"{"wasm": {"migrate": {"contract_addr": "treasury"}}}"#;
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        deny_list.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddGlobalAuthorization {
+            msg: matcher.to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+
+    let migrate = |contract_addr: &str| -> CosmosMsg {
+        WasmMsg::Migrate {
+            contract_addr: contract_addr.to_string(),
+            new_code_id: 2,
+            msg: to_binary(&Empty {}).unwrap(),
+        }
+        .into()
+    };
+    let is_authorized = |app: &App, addr: &Addr, sender: &str, msg: CosmosMsg| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked(sender).into(),
+                    msgs: vec![msg],
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+    for sender in ["Someone", "Someone_else"] {
+        assert!(!is_authorized(
+            &app,
+            &deny_list,
+            sender,
+            migrate("treasury")
+        ));
+        assert!(is_authorized(&app, &deny_list, sender, migrate("other")));
+    }
+
+    // Global matchers are evaluated alongside the per-sender ones
+    let allow_list = instantiate_filter(&mut app, Kind::Allow {});
+    for msg in [
+        ExecuteMsg::AddGlobalAuthorization {
+            msg: r#"{"bank": {}}"#.to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        },
+        ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: r#"{"wasm": {}}"#.to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: None,
+        },
+    ] {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            allow_list.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(msg),
+            &[],
+        )
+        .unwrap();
+    }
+    let send: CosmosMsg = BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1, "ujuno"),
+    }
+    .into();
+    assert!(is_authorized(&app, &allow_list, "Someone", send.clone()));
+    assert!(is_authorized(
+        &app,
+        &allow_list,
+        "Someone_else",
+        send.clone()
+    ));
+    assert!(is_authorized(
+        &app,
+        &allow_list,
+        "Someone",
+        migrate("other")
+    ));
+    assert!(!is_authorized(
+        &app,
+        &allow_list,
+        "Someone_else",
+        migrate("other")
+    ));
+
+    let response: AuthorizationsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            allow_list.clone(),
+            &AuthoriazationQueryMsg::<QueryMsg>::Extension(QueryMsg::GetGlobalAuthorizations {}),
+        )
+        .unwrap();
+    assert_eq!(response.authorizations.len(), 1);
+    assert_eq!(response.authorizations[0].addr, None);
+
+    // Only the owner can manage them
+    app.execute_contract(
+        Addr::unchecked("Someone"),
+        allow_list.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::RemoveGlobalAuthorization {
+            msg: r#"{"bank": {}}"#.to_string(),
+        }),
+        &[],
+    )
+    .unwrap_err();
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        allow_list.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::RemoveGlobalAuthorization {
+            msg: r#"{"bank": {}}"#.to_string(),
+        }),
+        &[],
+    )
+    .unwrap();
+    assert!(!is_authorized(&app, &allow_list, "Someone_else", send));
+}
+
+#[test]
+fn test_mixed_effects() {
+    use crate::state::{ConflictStrategy, Effect};
+
+    let mut app = App::default();
+    let addr = instantiate_filter(&mut app, Kind::Allow {});
+
+    // Allow all bank sends except to "bad"
+    for msg in [
+        ExecuteMsg::AddGlobalAuthorization {
+            msg: r#"{"bank": {"send": {}}}"#.to_string(),
+            effect: None,
+            priority: Some(1),
+            budget: None,
+            max_uses: None,
+        },
+        ExecuteMsg::AddGlobalAuthorization {
+            msg: r#"{"bank": {"send": {"to_address": "bad"}}}"#.to_string(),
+            effect: Some(Effect::Deny {}),
+            priority: None,
+            budget: None,
+            max_uses: None,
+        },
+    ] {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(msg),
+            &[],
+        )
+        .unwrap();
+    }
+
+    let is_authorized = |app: &App, to_address: &str| -> IsAuthorizedResponse {
+        app.wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs: vec![BankMsg::Send {
+                        to_address: to_address.to_string(),
+                        amount: coins(1, "ujuno"),
+                    }
+                    .into()],
+                },
+            )
+            .unwrap()
+    };
+    let set_strategy = |app: &mut App, strategy: ConflictStrategy| {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::SetConflictStrategy {
+                strategy,
+            }),
+            &[],
+        )
+        .unwrap();
+    };
+
+    // Deny overrides by default
+    assert!(is_authorized(&app, "good").authorized);
+    let response = is_authorized(&app, "bad");
+    assert!(!response.authorized);
+    assert_eq!(response.code.unwrap(), "rejected_by_matcher");
+
+    // A denied message can't be hidden in a batch with allowed ones
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &addr,
+            &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                context: Addr::unchecked("Someone").into(),
+                msgs: ["bad", "good"]
+                    .into_iter()
+                    .map(|to_address| {
+                        BankMsg::Send {
+                            to_address: to_address.to_string(),
+                            amount: coins(1, "ujuno"),
+                        }
+                        .into()
+                    })
+                    .collect(),
+            },
+        )
+        .unwrap();
+    assert!(!response.authorized);
+    assert_eq!(response.code.unwrap(), "rejected_by_matcher");
+
+    set_strategy(&mut app, ConflictStrategy::AllowOverrides {});
+    assert!(is_authorized(&app, "bad").authorized);
+
+    // The allow matcher has a higher priority
+    set_strategy(&mut app, ConflictStrategy::FirstMatch {});
+    assert!(is_authorized(&app, "bad").authorized);
+    assert!(is_authorized(&app, "good").authorized);
+}
+
+#[test]
+fn test_evaluation_modes() {
+    use crate::state::{Effect, EvaluationMode};
+
+    let mut app = App::default();
+    let addr = instantiate_filter(&mut app, Kind::Allow {});
+    for msg in [
+        r#"{"bank": {}}"#,
+        r#"{"staking": {}}"#,
+        r#"[{"staking": {}}, {"bank": {}}]"#,
+    ] {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked("Someone"),
+                msg: msg.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: None,
+            }),
+            &[],
+        )
+        .unwrap();
+    }
+
+    let send: CosmosMsg = BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1, "ujuno"),
+    }
+    .into();
+    let delegate: CosmosMsg = StakingMsg::Delegate {
+        validator: "validator".to_string(),
+        amount: coin(1, "ujuno"),
+    }
+    .into();
+    let is_authorized = |app: &App, msgs: Vec<CosmosMsg>| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs,
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+    let set_mode = |app: &mut App, mode: EvaluationMode| {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::SetEvaluationMode {
+                mode,
+            }),
+            &[],
+        )
+        .unwrap();
+    };
+
+    // By default a single matcher must match all the messages
+    assert!(is_authorized(&app, vec![send.clone(), send.clone()]));
+    assert!(!is_authorized(&app, vec![send.clone(), delegate.clone()]));
+
+    set_mode(&mut app, EvaluationMode::PerMessage {});
+    assert!(is_authorized(&app, vec![send.clone(), delegate.clone()]));
+    assert!(is_authorized(&app, vec![delegate.clone(), send.clone()]));
+
+    set_mode(&mut app, EvaluationMode::Sequence {});
+    assert!(is_authorized(&app, vec![delegate.clone(), send.clone()]));
+    assert!(!is_authorized(&app, vec![send.clone(), delegate.clone()]));
+    // Matchers that aren't arrays are checked against each message
+    assert!(is_authorized(&app, vec![send.clone()]));
+
+    // Deny matchers still apply to the messages of a sequence
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: r#"{"bank": {"send": {"to_address": "bad"}}}"#.to_string(),
+            effect: Some(Effect::Deny {}),
+            priority: None,
+            budget: None,
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+    let bad_send: CosmosMsg = BankMsg::Send {
+        to_address: "bad".to_string(),
+        amount: coins(1, "ujuno"),
+    }
+    .into();
+    assert!(!is_authorized(
+        &app,
+        vec![delegate.clone(), bad_send.clone()]
+    ));
+    assert!(is_authorized(&app, vec![delegate.clone(), send]));
+    set_mode(&mut app, EvaluationMode::SingleMatcher {});
+    assert!(!is_authorized(&app, vec![bad_send]));
+}
+
+#[test]
+fn test_budgets() {
+    use crate::msg::BudgetsResponse;
+    use crate::state::Budget;
+
+    let mut app = App::default();
+    let addr = instantiate_filter(&mut app, Kind::Allow {});
+    let add_budget = |app: &mut App, budget: Budget| {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked("Someone"),
+                msg: r#"{"bank": {"send": {}}}"#.to_string(),
+                effect: None,
+                priority: None,
+                budget: Some(budget),
+                max_uses: None,
+            }),
+            &[],
+        )
+    };
+    add_budget(
+        &mut app,
+        Budget {
+            limits: coins(10000, "ujuno"),
+            period: Some(3600),
+        },
+    )
+    .unwrap();
+    // A period of 0 would never reset
+    add_budget(
+        &mut app,
+        Budget {
+            limits: coins(10000, "ujuno"),
+            period: Some(0),
+        },
+    )
+    .unwrap_err();
+
+    let send = |amount: u128| -> CosmosMsg {
+        BankMsg::Send {
+            to_address: "you".to_string(),
+            amount: coins(amount, "ujuno"),
+        }
+        .into()
+    };
+    let is_authorized = |app: &App, msgs: Vec<CosmosMsg>| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs,
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+    let record = |app: &mut App, caller: &str, msgs: Vec<CosmosMsg>| {
+        app.execute_contract(
+            Addr::unchecked(caller),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::UpdateExecutedAuthorizationState {
+                msgs,
+                context: Addr::unchecked("Someone").into(),
+            },
+            &[],
+        )
+    };
+    let remaining = |app: &App| -> Uint128 {
+        let response: BudgetsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::Extension(QueryMsg::GetBudgets {
+                    sender: Addr::unchecked("Someone"),
+                }),
+            )
+            .unwrap();
+        response.budgets[0].remaining[0].amount
+    };
+
+    assert!(is_authorized(&app, vec![send(6000), send(4000)]));
+    assert!(!is_authorized(&app, vec![send(6000), send(4001)]));
+    // Other denoms aren't covered by the budget
+    assert!(!is_authorized(
+        &app,
+        vec![BankMsg::Send {
+            to_address: "you".to_string(),
+            amount: coins(1, "uatom"),
+        }
+        .into()]
+    ));
+
+    // Only the sender or an updater can record spends
+    record(&mut app, "Someone else", vec![send(6000)]).unwrap_err();
+    record(&mut app, "Someone", vec![send(6000)]).unwrap();
+    assert_eq!(remaining(&app), Uint128::new(4000));
+    assert!(is_authorized(&app, vec![send(4000)]));
+    assert!(!is_authorized(&app, vec![send(4001)]));
+
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::SetUpdaters {
+            updaters: vec!["executor".to_string()],
+        }),
+        &[],
+    )
+    .unwrap();
+    record(&mut app, "executor", vec![send(3000)]).unwrap();
+    assert_eq!(remaining(&app), Uint128::new(1000));
+    // Messages that aren't authorized don't spend anything
+    record(&mut app, "executor", vec![send(5000)]).unwrap();
+    assert_eq!(remaining(&app), Uint128::new(1000));
+
+    // The budget resets once the period is over
+    app.update_block(|block| block.time = block.time.plus_seconds(3600));
+    assert_eq!(remaining(&app), Uint128::new(10000));
+    assert!(is_authorized(&app, vec![send(10000)]));
+}
+
+#[test]
+fn test_stargate_budgets() {
+    use crate::decoders::proto::{Coin, Input, MsgMultiSend, MsgSend};
+    use crate::msg::BudgetsResponse;
+    use crate::state::Budget;
+    use prost::Message;
+
+    let mut app = App::default();
+    let addr = instantiate_filter_with(&mut app, Kind::Allow {}, true);
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+            addr: Addr::unchecked("Someone"),
+            msg: r#"{"stargate": {}}"#.to_string(),
+            effect: None,
+            priority: None,
+            budget: Some(Budget {
+                limits: coins(10, "ujuno"),
+                period: None,
+            }),
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+
+    let ujuno = |amount: u128| {
+        vec![Coin {
+            denom: "ujuno".to_string(),
+            amount: amount.to_string(),
+        }]
+    };
+    let send = |amount: u128| -> CosmosMsg {
+        CosmosMsg::Stargate {
+            type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+            value: Binary::from(
+                MsgSend {
+                    from_address: "Someone".to_string(),
+                    to_address: "you".to_string(),
+                    amount: ujuno(amount),
+                }
+                .encode_to_vec(),
+            ),
+        }
+    };
+    let multi_send = |amounts: &[u128]| -> CosmosMsg {
+        CosmosMsg::Stargate {
+            type_url: "/cosmos.bank.v1beta1.MsgMultiSend".to_string(),
+            value: Binary::from(
+                MsgMultiSend {
+                    inputs: amounts
+                        .iter()
+                        .map(|amount| Input {
+                            address: "Someone".to_string(),
+                            coins: ujuno(*amount),
+                        })
+                        .collect(),
+                    outputs: vec![],
+                }
+                .encode_to_vec(),
+            ),
+        }
+    };
+    let is_authorized = |app: &App, msg: CosmosMsg| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs: vec![msg],
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+
+    assert!(is_authorized(&app, send(10)));
+    assert!(!is_authorized(&app, send(1000000)));
+    assert!(is_authorized(&app, multi_send(&[4, 6])));
+    assert!(!is_authorized(&app, multi_send(&[4, 7])));
+    // A budget can't cover messages whose spend isn't known
+    assert!(!is_authorized(
+        &app,
+        CosmosMsg::Stargate {
+            type_url: "/cosmos.gov.v1beta1.MsgDeposit".to_string(),
+            value: Binary::default(),
+        }
+    ));
+
+    app.execute_contract(
+        Addr::unchecked("Someone"),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::UpdateExecutedAuthorizationState {
+            msgs: vec![send(6)],
+            context: Addr::unchecked("Someone").into(),
+        },
+        &[],
+    )
+    .unwrap();
+    let response: BudgetsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &addr,
+            &AuthoriazationQueryMsg::<QueryMsg>::Extension(QueryMsg::GetBudgets {
+                sender: Addr::unchecked("Someone"),
+            }),
+        )
+        .unwrap();
+    assert_eq!(response.budgets[0].remaining, coins(4, "ujuno"));
+    assert!(!is_authorized(&app, send(5)));
+}
+
+#[test]
+fn test_max_uses() {
+    use crate::state::EvaluationMode;
+
+    let mut app = App::default();
+    let addr = instantiate_filter(&mut app, Kind::Allow {});
+    let add_matcher = |app: &mut App, max_uses: u64| {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked("Someone"),
+                msg: r#"{"bank": {"send": {}}}"#.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: Some(max_uses),
+            }),
+            &[],
+        )
+    };
+    add_matcher(&mut app, 0).unwrap_err();
+    add_matcher(&mut app, 1).unwrap();
+
+    let send: CosmosMsg = BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1, "ujuno"),
+    }
+    .into();
+    let is_authorized = |app: &App, msgs: Vec<CosmosMsg>| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::IsAuthorized {
+                    context: Addr::unchecked("Someone").into(),
+                    msgs,
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+    let record = |app: &mut App, msgs: Vec<CosmosMsg>| {
+        app.execute_contract(
+            Addr::unchecked("Someone"),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::UpdateExecutedAuthorizationState {
+                msgs,
+                context: Addr::unchecked("Someone").into(),
+            },
+            &[],
+        )
+        .unwrap();
+    };
+    let matchers = |app: &App| -> Vec<Option<u64>> {
+        let response: AuthorizationsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::Extension(QueryMsg::GetAuthorizations {
+                    sender: Addr::unchecked("Someone"),
+                }),
+            )
+            .unwrap();
+        response.authorizations.iter().map(|a| a.max_uses).collect()
+    };
+
+    // A one-shot matcher authorizes a single execution and is then removed
+    assert!(is_authorized(&app, vec![send.clone()]));
+    record(&mut app, vec![send.clone()]);
+    assert_eq!(matchers(&app), vec![]);
+    assert!(!is_authorized(&app, vec![send.clone()]));
+
+    // When each message is evaluated on its own, each one uses the matcher
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::SetEvaluationMode {
+            mode: EvaluationMode::PerMessage {},
+        }),
+        &[],
+    )
+    .unwrap();
+    add_matcher(&mut app, 3).unwrap();
+    assert!(!is_authorized(
+        &app,
+        vec![send.clone(), send.clone(), send.clone(), send.clone()]
+    ));
+    record(&mut app, vec![send.clone(), send.clone()]);
+    assert_eq!(matchers(&app), vec![Some(1)]);
+    assert!(!is_authorized(&app, vec![send.clone(), send.clone()]));
+    record(&mut app, vec![send.clone()]);
+    assert_eq!(matchers(&app), vec![]);
+}
+
+#[test]
+fn test_global_max_uses() {
+    let mut app = App::default();
+    let addr = instantiate_filter(&mut app, Kind::Allow {});
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddGlobalAuthorization {
+            msg: r#"{"bank": {"send": {}}}"#.to_string(),
+            effect: None,
+            priority: None,
+            budget: None,
+            max_uses: Some(2),
+        }),
+        &[],
+    )
+    .unwrap();
+
+    let send: CosmosMsg = BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1, "ujuno"),
+    }
+    .into();
+    let record = |app: &mut App, caller: &str, sender: &str| {
+        app.execute_contract(
+            Addr::unchecked(caller),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::UpdateExecutedAuthorizationState {
+                msgs: vec![send.clone()],
+                context: Addr::unchecked(sender).into(),
+            },
+            &[],
+        )
+    };
+    let uses = |app: &App| -> Vec<Option<u64>> {
+        let response: AuthorizationsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<QueryMsg>::Extension(
+                    QueryMsg::GetGlobalAuthorizations {},
+                ),
+            )
+            .unwrap();
+        response.authorizations.iter().map(|a| a.max_uses).collect()
+    };
+
+    // Anyone can record its own executions, but that doesn't use up the
+    // uses everyone shares
+    let err = record(&mut app, "Stranger", "Stranger").unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("Only an updater can use up global matchers"));
+    assert_eq!(uses(&app), vec![Some(2)]);
+
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::SetUpdaters {
+            updaters: vec!["executor".to_string()],
+        }),
+        &[],
+    )
+    .unwrap();
+    record(&mut app, "executor", "Someone").unwrap();
+    assert_eq!(uses(&app), vec![Some(1)]);
+    record(&mut app, "executor", "Stranger").unwrap();
+    assert_eq!(uses(&app), vec![]);
+}
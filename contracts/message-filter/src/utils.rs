@@ -1,9 +1,11 @@
-use cosmwasm_std::{CosmosMsg, StdError, Uint128};
+use cosmwasm_std::{Binary, CosmosMsg, CustomMsg, StdError, Uint128};
 use schemars::{JsonSchema, Map};
 use serde::{Deserialize, Serialize};
 
 use serde_json_wasm::{from_str, to_string};
 
+use crate::pattern::Pattern;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 #[serde(untagged)]
@@ -17,7 +19,9 @@ pub enum Value {
     Object(Map<String, Value>),
 }
 
-pub fn msg_to_value(msg: &CosmosMsg) -> Result<Value, StdError> {
+/// Converts a message to its json Value. Custom messages are serialized as
+/// `{"custom": ...}`, so matchers can inspect chain-specific payloads too.
+pub fn msg_to_value<C: CustomMsg>(msg: &CosmosMsg<C>) -> Result<Value, StdError> {
     let serialized = to_string(msg).map_err(|_| StdError::ParseErr {
         target_type: "Value".to_string(),
         msg: "invalid CosmosMsg".to_string(),
@@ -33,50 +37,48 @@ pub fn str_to_value(msg: &str) -> Result<Value, StdError> {
     })
 }
 
-pub fn deep_partial_match(msg: &Value, authorization: &Value) -> bool {
-    match authorization {
-        Value::Object(auth_map) => {
-            if auth_map.is_empty() {
-                return true;
-            }
-
-            let mut matching = true;
-            if let Value::Object(msg_map) = msg {
-                for (key, val) in auth_map {
-                    if !msg_map.contains_key(key) {
-                        return false;
-                    };
-                    matching = matching & deep_partial_match(msg_map.get(key).unwrap(), val);
-                }
-            } else {
-                return false;
-            }
-            matching
-        }
-        Value::Array(auth_array) => {
-            // Comparing arrays manually because PartialEq doesn't understand use our deep matching.
-            let mut matching = true;
-            if let Value::Array(msg_array) = msg {
-                if msg_array.len() != auth_array.len() {
-                    return false;
-                }
-                for (i, elem) in auth_array.iter().enumerate() {
-                    matching = matching & deep_partial_match(&msg_array[i], &elem);
-                }
-            } else {
-                return false;
-            }
-            matching
-        }
-        _ => authorization == msg,
+/// Replaces every string holding base64 encoded json (i.e.: a `Binary`
+/// contract message) with its decoded Value. The decoded values are decoded
+/// again, so payloads nested inside other payloads can be matched as well.
+pub fn decode_binaries(value: Value) -> Value {
+    match value {
+        Value::String(s) => match decode_binary(&s) {
+            Some(decoded) => decode_binaries(decoded),
+            None => Value::String(s),
+        },
+        Value::Array(array) => Value::Array(array.into_iter().map(decode_binaries).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, val)| (key, decode_binaries(val)))
+                .collect(),
+        ),
+        value => value,
     }
 }
 
+/// Only json objects and arrays are decoded. Any other string (addresses,
+/// denoms, ...) could happen to be valid base64, so it is left untouched.
+fn decode_binary(s: &str) -> Option<Value> {
+    let bytes = Binary::from_base64(s).ok()?;
+    let json = std::str::from_utf8(bytes.as_slice()).ok()?;
+    match str_to_value(json).ok()? {
+        value @ (Value::Object(_) | Value::Array(_)) => Some(value),
+        _ => None,
+    }
+}
+
+/// Checks if msg matches the json authorization. Authorizations that aren't
+/// valid matchers never match. See `Pattern` for the supported syntax.
+pub fn deep_partial_match(msg: &Value, authorization: &Value) -> bool {
+    Pattern::parse(authorization).is_ok_and(|pattern| pattern.matches(msg))
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
-    use cosmwasm_std::{coins, BankMsg, CosmosMsg};
+    use cosmwasm_std::{coins, BankMsg, CosmosMsg, WasmMsg};
     use serde_json_wasm::from_str;
 
     #[test]
@@ -209,4 +211,139 @@ mod tests {
             false
         );
     }
+
+    #[test]
+    fn test_decode_binaries() {
+        let inner = Binary::from(br#"{"transfer": {"amount": "1"}}"#);
+        let outer = Binary::from(format!(r#"{{"execute": {{"msg": "{}"}}}}"#, inner).as_bytes());
+        let msg: CosmosMsg = WasmMsg::Execute {
+            contract_addr: "proxy".to_string(),
+            msg: outer,
+            funds: vec![],
+        }
+        .into();
+        let value = decode_binaries(msg_to_value(&msg).unwrap());
+
+        // Nested payloads are decoded
+        assert_eq!(
+            deep_partial_match(
+                &value,
+                &from_str(
+                    r#"{"wasm": {"execute": {"contract_addr": "proxy", "msg": {"execute": {"msg": {"transfer": {}}}}}}}"#
+                )
+                .unwrap(),
+            ),
+            true
+        );
+
+        // Strings that are valid base64 but not json are left untouched
+        let value = decode_binaries(from_str(r#"{"denom": "juno"}"#).unwrap());
+        assert_eq!(value, from_str(r#"{"denom": "juno"}"#).unwrap());
+
+        // Without decoding the payload is opaque
+        assert_eq!(
+            deep_partial_match(
+                &msg_to_value(&msg).unwrap(),
+                &from_str(r#"{"wasm": {"execute": {"msg": {"execute": {}}}}}"#).unwrap(),
+            ),
+            false
+        );
+    }
+
+    #[test]
+    fn test_operators() {
+        let msg: CosmosMsg = BankMsg::Send {
+            to_address: "juno1abc".to_string(),
+            amount: coins(1000, "ujuno"),
+        }
+        .into();
+        let msg = msg_to_value(&msg).unwrap();
+        let matches = |matcher: &str| deep_partial_match(&msg, &from_str(matcher).unwrap());
+
+        // Uint128 strings are compared numerically
+        assert!(matches(
+            r#"{"bank": {"send": {"amount": [{"amount": {"$lte": "1000"}}]}}}"#
+        ));
+        assert!(!matches(
+            r#"{"bank": {"send": {"amount": [{"amount": {"$lte": "999"}}]}}}"#
+        ));
+        assert!(matches(
+            r#"{"bank": {"send": {"amount": [{"amount": {"$gte": "200", "$lte": "10000"}}]}}}"#
+        ));
+        assert!(!matches(
+            r#"{"bank": {"send": {"amount": [{"amount": {"$gte": "1001"}}]}}}"#
+        ));
+
+        assert!(matches(
+            r#"{"bank": {"send": {"to_address": {"$in": ["juno1xyz", "juno1abc"]}}}}"#
+        ));
+        assert!(!matches(
+            r#"{"bank": {"send": {"to_address": {"$in": ["juno1xyz"]}}}}"#
+        ));
+        assert!(matches(
+            r#"{"bank": {"send": {"to_address": {"$not": "juno1xyz"}}}}"#
+        ));
+        assert!(!matches(
+            r#"{"bank": {"send": {"to_address": {"$not": {"$prefix": "juno1"}}}}}"#
+        ));
+        assert!(matches(
+            r#"{"bank": {"send": {"to_address": {"$prefix": "juno1"}}}}"#
+        ));
+        assert!(matches(
+            r#"{"bank": {"send": {"to_address": {"$regex": "^juno1[a-z]+$"}}}}"#
+        ));
+        assert!(!matches(
+            r#"{"bank": {"send": {"to_address": {"$regex": "^osmo1"}}}}"#
+        ));
+
+        // Unknown operators never match
+        assert!(!matches(
+            r#"{"bank": {"send": {"to_address": {"$eq": "juno1abc"}}}}"#
+        ));
+    }
+
+    #[test]
+    fn test_array_operators() {
+        let msg: Value = from_str(
+            r#"{"amount": [{"denom": "ujuno", "amount": "10"}, {"denom": "uatom", "amount": "20"}]}"#,
+        )
+        .unwrap();
+        let matches = |matcher: &str| deep_partial_match(&msg, &from_str(matcher).unwrap());
+
+        // Positional matching fails as soon as there is more than one coin
+        assert!(!matches(r#"{"amount": [{"denom": "ujuno"}]}"#));
+
+        assert!(matches(r#"{"amount": {"$any": {"denom": "ujuno"}}}"#));
+        assert!(!matches(r#"{"amount": {"$any": {"denom": "uosmo"}}}"#));
+
+        assert!(matches(
+            r#"{"amount": {"$all": {"amount": {"$lte": "20"}}}}"#
+        ));
+        assert!(!matches(r#"{"amount": {"$all": {"denom": "ujuno"}}}"#));
+
+        assert!(matches(
+            r#"{"amount": {"$contains": [{"denom": "uatom"}]}}"#
+        ));
+        assert!(matches(
+            r#"{"amount": {"$contains": [{"denom": "uatom"}, {"denom": "ujuno"}]}}"#
+        ));
+        // Each matcher needs its own element
+        assert!(!matches(r#"{"amount": {"$contains": [{}, {}, {}]}}"#));
+
+        assert!(matches(
+            r#"{"amount": {"$unordered": [{"denom": "uatom"}, {"denom": "ujuno"}]}}"#
+        ));
+        assert!(!matches(
+            r#"{"amount": {"$unordered": [{"denom": "uatom"}]}}"#
+        ));
+        // A greedy assignment would use the first coin for {} and fail
+        assert!(matches(
+            r#"{"amount": {"$unordered": [{}, {"denom": "ujuno"}]}}"#
+        ));
+
+        // Array operators don't match other types
+        assert!(!matches(
+            r#"{"amount": {"$any": {}}, "denom": {"$any": {}}}"#
+        ));
+    }
 }
@@ -1,52 +1,144 @@
-#[cfg(not(feature = "library"))]
-use cosmwasm_std::entry_point;
+use std::marker::PhantomData;
+
 use cosmwasm_std::{
-    Addr, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdError,
+    Addr, CosmosMsg, CustomMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdError,
     StdResult,
 };
-use cw2::set_contract_version;
+use cw_authorizations::error::{EmptyError, QUERY_FAILED_CODE, UNAUTHORIZED_CODE};
+use cw_authorizations::migration::Version;
 use cw_authorizations::msg::{
-    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, IsAuthorizedResponse,
+    AuthoriazationQueryMsg, AuthorizationContext, AuthorizationRequest, FailurePolicy,
+    IsAuthorizedBatchResponse, IsAuthorizedPerMessageResponse, IsAuthorizedResponse,
 };
+use cw_authorizations::validation::{validate_child, validate_edge, DEFAULT_MAX_DEPTH};
 use cw_authorizations::{Authorization, AuthorizationError};
+use cw_authorizations_derive::authorization_contract;
 
-use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::migrations::migrate_from_v0_1;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg};
 use crate::state::ProxyState;
 
-const CONTRACT_NAME: &str = "crates.io:satisfies-all";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-
-pub struct SatisfiesAllContract {
+/// `C` is the custom message type of the chain. Messages are forwarded to the
+/// children as `CosmosMsg<C>`.
+pub struct SatisfiesAllContract<C = Empty> {
     state: ProxyState,
+    custom: PhantomData<C>,
 }
 
-impl SatisfiesAllContract {
+impl<C> SatisfiesAllContract<C> {
     pub fn instantiate(
         &self,
         deps: DepsMut,
         msg: InstantiateMsg,
     ) -> Result<(), AuthorizationError> {
-        self.state.admin.save(deps.storage, &msg.admin)?;
+        self.state.ownership.initialize(deps.storage, msg.owner)?;
         self.state.parent.save(deps.storage, &msg.parent)?;
+        let max_depth = msg.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+        self.state.max_depth.save(deps.storage, &max_depth)?;
         for child in msg.children {
+            let child = validate_child(deps.as_ref(), &child)?;
+            validate_edge(deps.as_ref(), None, &child, max_depth)?;
             self.state.children.save(deps.storage, child, &Empty {})?;
         }
         Ok(())
     }
+
+    pub fn migrate(
+        &self,
+        deps: DepsMut,
+        _msg: MigrateMsg,
+        from_version: &Version,
+    ) -> Result<(), AuthorizationError> {
+        if *from_version < Version::new(0, 2, 0) {
+            migrate_from_v0_1(deps.storage, &self.state)?;
+        }
+        Ok(())
+    }
+
+    /// Combines the verdicts of all children for `count` independent checks.
+    /// `query` asks a child about the checks at the given indices and returns
+    /// one verdict per index. A check is authorized if every child authorizes
+    /// it.
+    fn combine_children<Q>(
+        &self,
+        deps: Deps,
+        count: usize,
+        query: Q,
+    ) -> Result<Vec<IsAuthorizedResponse>, AuthorizationError>
+    where
+        Q: Fn(&Addr, &[usize]) -> StdResult<Vec<IsAuthorizedResponse>>,
+    {
+        let children: Vec<(Addr, Empty)> = self
+            .state
+            .children
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<Result<_, StdError>>()?;
+
+        let mut responses = vec![IsAuthorizedResponse::authorized(); count];
+        for child in children.into_iter().map(|c| c.0) {
+            // Only the checks that every previous child authorized need to be
+            // sent
+            let pending: Vec<usize> = (0..count).filter(|&i| responses[i].authorized).collect();
+            if pending.is_empty() {
+                break;
+            }
+
+            // A child that doesn't return one verdict per check is treated
+            // like a failed query so that missing verdicts aren't authorized
+            let child_responses = query(&child, &pending)
+                .and_then(|responses| {
+                    if responses.len() == pending.len() {
+                        Ok(responses)
+                    } else {
+                        Err(StdError::generic_err(format!(
+                            "Expected {} verdicts but got {}",
+                            pending.len(),
+                            responses.len()
+                        )))
+                    }
+                })
+                .unwrap_or_else(|e| {
+                    vec![
+                        IsAuthorizedResponse::unauthorized(QUERY_FAILED_CODE, Some(e.to_string()));
+                        pending.len()
+                    ]
+                });
+            for (i, response) in pending.into_iter().zip(child_responses) {
+                if !response.authorized {
+                    responses[i] = IsAuthorizedResponse::unauthorized(
+                        UNAUTHORIZED_CODE,
+                        Some(format!(
+                            "{} did not authorize the messages: {}",
+                            child,
+                            response.reason.or(response.code).unwrap_or_default()
+                        )),
+                    );
+                }
+            }
+        }
+        Ok(responses)
+    }
 }
 
-impl Authorization<ExecuteMsg> for SatisfiesAllContract {
+#[authorization_contract(instantiate = InstantiateMsg, execute = ExecuteMsg, migrate = MigrateMsg)]
+impl<C: CustomMsg> Authorization<ExecuteMsg, Empty, EmptyError, C> for SatisfiesAllContract<C> {
     fn new() -> Self {
         SatisfiesAllContract {
             state: ProxyState::new(),
+            custom: PhantomData,
         }
     }
 
+    fn contract_type(&self) -> String {
+        "satisfies_all".to_string()
+    }
+
     fn is_authorized(
         &self,
         deps: Deps,
-        msgs: &Vec<CosmosMsg>,
-        sender: &Addr,
+        _env: &Env,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
     ) -> Result<bool, AuthorizationError> {
         let children: Vec<(Addr, Empty)> = self
             .state
@@ -55,18 +147,80 @@ impl Authorization<ExecuteMsg> for SatisfiesAllContract {
             .collect::<Result<_, StdError>>()?;
 
         // This checks all the registered authorizations return true
-        Ok(children.into_iter().map(|c| c.0).all(|a| {
-            deps.querier
+        for child in children.into_iter().map(|c| c.0) {
+            let response = deps
+                .querier
                 .query_wasm_smart(
-                    a.clone(),
-                    &AuthoriazationQueryMsg::IsAuthorized::<Empty> {
+                    child.clone(),
+                    &AuthoriazationQueryMsg::IsAuthorized::<Empty, C> {
                         msgs: msgs.to_owned(),
-                        sender: sender.to_owned(),
+                        context: context.to_owned(),
+                    },
+                )
+                .unwrap_or_else(|e| {
+                    IsAuthorizedResponse::unauthorized(QUERY_FAILED_CODE, Some(e.to_string()))
+                });
+            if !response.authorized {
+                return Err(AuthorizationError::Unauthorized {
+                    reason: Some(format!(
+                        "{} did not authorize the messages: {}",
+                        child,
+                        response.reason.or(response.code).unwrap_or_default()
+                    )),
+                });
+            }
+        }
+        Ok(true)
+    }
+
+    fn is_authorized_batch(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        requests: &[AuthorizationRequest<C>],
+    ) -> Result<Vec<IsAuthorizedResponse>, AuthorizationError> {
+        self.combine_children(deps, requests.len(), |child, pending| {
+            deps.querier
+                .query_wasm_smart::<IsAuthorizedBatchResponse>(
+                    child,
+                    &AuthoriazationQueryMsg::IsAuthorizedBatch::<Empty, C> {
+                        requests: pending.iter().map(|&i| requests[i].clone()).collect(),
+                    },
+                )
+                .map(|r| r.responses)
+        })
+    }
+
+    fn is_authorized_per_message(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        msgs: &[CosmosMsg<C>],
+        context: &AuthorizationContext,
+    ) -> Result<Vec<IsAuthorizedResponse>, AuthorizationError> {
+        self.combine_children(deps, msgs.len(), |child, pending| {
+            deps.querier
+                .query_wasm_smart::<IsAuthorizedPerMessageResponse>(
+                    child,
+                    &AuthoriazationQueryMsg::IsAuthorizedPerMessage::<Empty, C> {
+                        msgs: pending.iter().map(|&i| msgs[i].clone()).collect(),
+                        context: context.clone(),
                     },
                 )
-                .unwrap_or(IsAuthorizedResponse { authorized: false })
-                .authorized
-        }))
+                .map(|r| r.responses)
+        })
+    }
+
+    fn failure_policy(
+        &self,
+        deps: Deps,
+        child: &Addr,
+    ) -> Result<FailurePolicy, AuthorizationError> {
+        Ok(self
+            .state
+            .policies
+            .may_load(deps.storage, child.clone())?
+            .unwrap_or_default())
     }
 
     fn get_sub_authorizations(&self, deps: Deps) -> Result<Vec<Addr>, AuthorizationError> {
@@ -79,56 +233,62 @@ impl Authorization<ExecuteMsg> for SatisfiesAllContract {
             .collect())
     }
 
+    fn update_authorization_state(
+        &self,
+        deps: DepsMut,
+        _env: &Env,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
+        real_sender: &Addr,
+    ) -> Result<Response, AuthorizationError> {
+        // Children trust updates coming from this contract, so only the
+        // parent can forward updates on behalf of someone else
+        let parent = self.state.parent.load(deps.storage)?;
+        if &context.sender != real_sender && real_sender != &parent {
+            return Err(AuthorizationError::Unauthorized {
+                reason: Some("Auth updates that aren't triggered by a parent contract cannot specify a sender other than the caller".to_string()),
+            });
+        }
+
+        let sub_msgs = self.generate_child_update_msgs(deps, msgs, context)?;
+        Ok(Response::default().add_submessages(sub_msgs))
+    }
+
     fn execute_extension(
         &self,
         deps: DepsMut,
-        _env: Env,
+        env: Env,
         info: MessageInfo,
         msg: ExecuteMsg,
     ) -> Result<Response, AuthorizationError> {
-        if info.sender != self.state.admin.load(deps.storage)? {
-            return Err(AuthorizationError::Unauthorized {
-                //reason: Some("Only the parent can execute on this contract".to_string()),
-            }
-            .into());
-        }
+        self.state
+            .ownership
+            .assert_owner(deps.storage, &info.sender)?;
 
         match msg {
             ExecuteMsg::AddChild { addr } => {
+                let addr = validate_child(deps.as_ref(), &addr)?;
+                let max_depth = self
+                    .state
+                    .max_depth
+                    .may_load(deps.storage)?
+                    .unwrap_or(DEFAULT_MAX_DEPTH);
+                validate_edge(deps.as_ref(), Some(&env.contract.address), &addr, max_depth)?;
                 self.state.children.save(deps.storage, addr, &Empty {})?;
                 Ok(Response::default().add_attribute("action", "allow"))
             }
             ExecuteMsg::RemoveChild { addr } => {
-                self.state.children.remove(deps.storage, addr);
+                self.state.children.remove(deps.storage, addr.clone());
+                self.state.policies.remove(deps.storage, addr);
                 Ok(Response::default().add_attribute("action", "remove"))
             }
+            ExecuteMsg::SetFailurePolicy { child, policy } => {
+                if !self.state.children.has(deps.storage, child.clone()) {
+                    return Err(AuthorizationError::Std(StdError::not_found("child")));
+                }
+                self.state.policies.save(deps.storage, child, &policy)?;
+                Ok(Response::default().add_attribute("action", "set_failure_policy"))
+            }
         }
     }
 }
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    msg: InstantiateMsg,
-) -> Result<Response, AuthorizationError> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    SatisfiesAllContract::new().instantiate(deps, msg)?;
-    Ok(Response::default().add_attribute("action", "instantiate"))
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: AuthoriazationExecuteMsg<ExecuteMsg>,
-) -> Result<Response, AuthorizationError> {
-    SatisfiesAllContract::new().execute(deps, env, info, msg)
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: AuthoriazationQueryMsg) -> StdResult<Binary> {
-    SatisfiesAllContract::new().query(deps, env, msg)
-}
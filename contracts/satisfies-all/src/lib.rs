@@ -1,3 +1,7 @@
 pub mod contract;
+mod migrations;
 pub mod msg;
 pub mod state;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,18 @@
+use cosmwasm_std::{Addr, Storage};
+use cw_authorizations::AuthorizationError;
+use cw_storage_plus::Item;
+
+use crate::state::ProxyState;
+
+/// Before 0.2.0 the owner was stored under the "admin" key
+const ADMIN_V0_1: Item<Addr> = Item::new("admin");
+
+pub fn migrate_from_v0_1(
+    storage: &mut dyn Storage,
+    state: &ProxyState,
+) -> Result<(), AuthorizationError> {
+    let admin = ADMIN_V0_1.load(storage)?;
+    ADMIN_V0_1.remove(storage);
+    state.ownership.initialize(storage, admin)?;
+    Ok(())
+}
@@ -1,24 +1,38 @@
 use cosmwasm_std::{Addr, CustomMsg};
+use cw_authorizations::msg::FailurePolicy;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
-    pub admin: Addr,
+    pub owner: Addr,
     pub parent: Addr,
     pub children: Vec<Addr>,
+    /// The maximum depth of the subtree rooted at this contract, checked when
+    /// children are added. Levels above this contract aren't counted. Defaults
+    /// to `DEFAULT_MAX_DEPTH`
+    pub max_depth: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    AddChild { addr: Addr },
-    RemoveChild { addr: Addr },
+    AddChild {
+        addr: Addr,
+    },
+    RemoveChild {
+        addr: Addr,
+    },
+    /// Sets what to do when updating the state of a child fails
+    SetFailurePolicy {
+        child: Addr,
+        policy: FailurePolicy,
+    },
 }
 
 impl CustomMsg for ExecuteMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {}
@@ -0,0 +1,1112 @@
+use cosmwasm_std::{
+    coin, coins, to_binary, Addr, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Empty, Env,
+    MessageInfo, Response, StakingMsg, StdError, StdResult,
+};
+use cw_authorizations::msg::{
+    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, AuthorizationRequest, DecisionNode,
+    FailedUpdatesResponse, FailurePolicy, InfoResponse, IsAuthorizedBatchResponse,
+    IsAuthorizedPerMessageResponse, IsAuthorizedResponse,
+};
+use cw_authorizations::ownership::{Ownership, OwnershipAction};
+use cw_authorizations::INTERFACE_VERSION;
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
+
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg};
+
+const CREATOR: &str = "creator";
+const MEMBER: &str = "member";
+
+fn satisfies_all() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_reply(crate::contract::reply)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn satisfies_any() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        satisfies_any::contract::execute,
+        satisfies_any::contract::instantiate,
+        satisfies_any::contract::query,
+    )
+    .with_reply(satisfies_any::contract::reply)
+    .with_migrate(satisfies_any::contract::migrate);
+    Box::new(contract)
+}
+
+fn message_filter() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        message_filter::contract::execute,
+        message_filter::contract::instantiate,
+        message_filter::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn whitelist() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        whitelist::contract::execute,
+        whitelist::contract::instantiate,
+        whitelist::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn short_verdicts_query(
+    _deps: Deps,
+    _env: Env,
+    msg: AuthoriazationQueryMsg<Empty>,
+) -> StdResult<Binary> {
+    match msg {
+        AuthoriazationQueryMsg::Info {} => to_binary(&InfoResponse {
+            contract_type: "short_verdicts".to_string(),
+            contract_name: "short-verdicts".to_string(),
+            contract_version: "0.1.0".to_string(),
+            interface_version: INTERFACE_VERSION.to_string(),
+            children: vec![],
+            stateful: false,
+        }),
+        AuthoriazationQueryMsg::IsAuthorized { .. } => {
+            to_binary(&IsAuthorizedResponse::authorized())
+        }
+        AuthoriazationQueryMsg::IsAuthorizedBatch { .. } => {
+            to_binary(&IsAuthorizedBatchResponse { responses: vec![] })
+        }
+        AuthoriazationQueryMsg::IsAuthorizedPerMessage { .. } => {
+            to_binary(&IsAuthorizedPerMessageResponse { responses: vec![] })
+        }
+        _ => Err(StdError::generic_err("unsupported")),
+    }
+}
+
+/// A misbehaving child that authorizes everything but returns no verdicts for
+/// batch and per-message queries
+fn short_verdicts() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        short_verdicts_query,
+    );
+    Box::new(contract)
+}
+
+/// Builds the tree all[whitelist, any[message-filter]] where MEMBER is
+/// whitelisted and allowed to send bank messages.
+fn setup_tree(app: &mut App) -> (Addr, Addr, Addr, Addr) {
+    let creator = Addr::unchecked(CREATOR);
+
+    let whitelist_id = app.store_code(whitelist());
+    let whitelist_addr = app
+        .instantiate_contract(
+            whitelist_id,
+            creator.clone(),
+            &whitelist::msg::InstantiateMsg {
+                owner: creator.clone(),
+            },
+            &[],
+            "Whitelist",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        creator.clone(),
+        whitelist_addr.clone(),
+        &AuthoriazationExecuteMsg::<whitelist::msg::ExecuteMsg>::Extension(
+            whitelist::msg::ExecuteMsg::Allow {
+                addr: MEMBER.to_string(),
+                max_uses: None,
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+
+    let filter_id = app.store_code(message_filter());
+    let filter_addr = app
+        .instantiate_contract(
+            filter_id,
+            creator.clone(),
+            &message_filter::msg::InstantiateMsg {
+                owner: creator.clone(),
+                kind: message_filter::state::Kind::Allow {},
+                decode_binaries: false,
+                conflict_strategy: Default::default(),
+                evaluation_mode: Default::default(),
+            },
+            &[],
+            "Message Filter",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        creator.clone(),
+        filter_addr.clone(),
+        &AuthoriazationExecuteMsg::<message_filter::msg::ExecuteMsg>::Extension(
+            message_filter::msg::ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked(MEMBER),
+                msg: r#"{"bank": {}}"#.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: None,
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+
+    let any_id = app.store_code(satisfies_any());
+    let any_addr = app
+        .instantiate_contract(
+            any_id,
+            creator.clone(),
+            &satisfies_any::msg::InstantiateMsg {
+                owner: creator.clone(),
+                parent: creator.clone(),
+                children: vec![filter_addr.clone()],
+                max_depth: None,
+            },
+            &[],
+            "Satisfies Any",
+            None,
+        )
+        .unwrap();
+
+    let all_id = app.store_code(satisfies_all());
+    let all_addr = app
+        .instantiate_contract(
+            all_id,
+            creator.clone(),
+            &InstantiateMsg {
+                owner: creator.clone(),
+                parent: creator,
+                children: vec![whitelist_addr.clone(), any_addr.clone()],
+                max_depth: None,
+            },
+            &[],
+            "Satisfies All",
+            None,
+        )
+        .unwrap();
+
+    (all_addr, whitelist_addr, any_addr, filter_addr)
+}
+
+#[test]
+fn test_explain() {
+    let mut app = App::default();
+    let (all_addr, whitelist_addr, any_addr, filter_addr) = setup_tree(&mut app);
+
+    let bank_msgs: Vec<CosmosMsg> = vec![BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1015, "earth"),
+    }
+    .into()];
+    let decision: DecisionNode = app
+        .wrap()
+        .query_wasm_smart(
+            all_addr.clone(),
+            &AuthoriazationQueryMsg::<Empty>::Explain {
+                msgs: bank_msgs,
+                context: Addr::unchecked(MEMBER).into(),
+            },
+        )
+        .unwrap();
+    assert_eq!(decision.contract, all_addr);
+    assert_eq!(decision.contract_type, "satisfies_all");
+    assert!(decision.verdict.authorized);
+    assert_eq!(decision.children.len(), 2);
+
+    let staking_msgs: Vec<CosmosMsg> = vec![StakingMsg::Delegate {
+        validator: "validator".to_string(),
+        amount: coin(1, "earth"),
+    }
+    .into()];
+    let decision: DecisionNode = app
+        .wrap()
+        .query_wasm_smart(
+            all_addr,
+            &AuthoriazationQueryMsg::<Empty>::Explain {
+                msgs: staking_msgs,
+                context: Addr::unchecked(MEMBER).into(),
+            },
+        )
+        .unwrap();
+    assert!(!decision.verdict.authorized);
+
+    let whitelist_node = decision
+        .children
+        .iter()
+        .find(|c| c.contract == whitelist_addr)
+        .unwrap();
+    assert_eq!(whitelist_node.contract_type, "whitelist");
+    assert!(whitelist_node.verdict.authorized);
+    assert!(whitelist_node.children.is_empty());
+
+    // The rejection comes from the filter nested under satisfies-any
+    let any_node = decision
+        .children
+        .iter()
+        .find(|c| c.contract == any_addr)
+        .unwrap();
+    assert_eq!(any_node.contract_type, "satisfies_any");
+    assert!(!any_node.verdict.authorized);
+    assert_eq!(any_node.children.len(), 1);
+
+    let filter_node = &any_node.children[0];
+    assert_eq!(filter_node.contract, filter_addr);
+    assert_eq!(filter_node.contract_type, "message_filter");
+    assert_eq!(
+        filter_node.verdict.code,
+        Some("no_matching_authorization".to_string())
+    );
+}
+
+#[test]
+fn test_child_update_errors_are_handled() {
+    let mut app = App::default();
+    let (all_addr, _, _, _) = setup_tree(&mut app);
+
+    // satisfies-any rejects updates from anyone other than its parent that
+    // specify a different sender, so updating it through satisfies-all fails.
+    // The failure is reported through the reply instead of reverting.
+    let response = app
+        .execute_contract(
+            Addr::unchecked(CREATOR),
+            all_addr,
+            &AuthoriazationExecuteMsg::<Empty>::UpdateExecutedAuthorizationState {
+                msgs: vec![],
+                context: Addr::unchecked(MEMBER).into(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(response
+        .events
+        .iter()
+        .flat_map(|e| e.attributes.iter())
+        .any(|a| a.key == "update_error"));
+}
+
+#[test]
+fn test_incompatible_children_are_rejected() {
+    let mut app = App::default();
+    let (all_addr, _, _, filter_addr) = setup_tree(&mut app);
+    let creator = Addr::unchecked(CREATOR);
+
+    // MEMBER is not a contract, so it can't be an authorization
+    let err = app
+        .execute_contract(
+            creator.clone(),
+            all_addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddChild {
+                addr: Addr::unchecked(MEMBER),
+            }),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("is not a compatible authorization"));
+
+    app.execute_contract(
+        creator.clone(),
+        all_addr,
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddChild {
+            addr: filter_addr,
+        }),
+        &[],
+    )
+    .unwrap();
+
+    let all_id = app.store_code(satisfies_all());
+    let err = app
+        .instantiate_contract(
+            all_id,
+            creator.clone(),
+            &InstantiateMsg {
+                owner: creator.clone(),
+                parent: creator,
+                children: vec![Addr::unchecked(MEMBER)],
+                max_depth: None,
+            },
+            &[],
+            "Satisfies All",
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("is not a compatible authorization"));
+}
+
+#[test]
+fn test_info() {
+    let mut app = App::default();
+    let (all_addr, whitelist_addr, any_addr, filter_addr) = setup_tree(&mut app);
+
+    let info: InfoResponse = app
+        .wrap()
+        .query_wasm_smart(all_addr, &AuthoriazationQueryMsg::<Empty>::Info {})
+        .unwrap();
+    assert_eq!(info.contract_type, "satisfies_all");
+    assert_eq!(info.contract_name, "crates.io:satisfies-all");
+    assert_eq!(info.contract_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(info.interface_version, INTERFACE_VERSION);
+    assert_eq!(info.children.len(), 2);
+    assert!(info.children.contains(&whitelist_addr));
+    assert!(info.children.contains(&any_addr));
+    assert!(!info.stateful);
+
+    let info: InfoResponse = app
+        .wrap()
+        .query_wasm_smart(filter_addr, &AuthoriazationQueryMsg::<Empty>::Info {})
+        .unwrap();
+    assert_eq!(info.contract_type, "message_filter");
+    assert_eq!(info.contract_name, "crates.io:message-filter");
+    assert!(info.children.is_empty());
+}
+
+#[test]
+fn test_cycles_are_rejected() {
+    let mut app = App::default();
+    let (all_addr, _, any_addr, _) = setup_tree(&mut app);
+    let creator = Addr::unchecked(CREATOR);
+
+    // A contract can't be its own child
+    let err = app
+        .execute_contract(
+            creator.clone(),
+            any_addr.clone(),
+            &AuthoriazationExecuteMsg::<satisfies_any::msg::ExecuteMsg>::Extension(
+                satisfies_any::msg::ExecuteMsg::AddChild {
+                    addr: any_addr.clone(),
+                },
+            ),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("would create a cycle"));
+
+    // satisfies-all is an ancestor of satisfies-any
+    let err = app
+        .execute_contract(
+            creator,
+            any_addr,
+            &AuthoriazationExecuteMsg::<satisfies_any::msg::ExecuteMsg>::Extension(
+                satisfies_any::msg::ExecuteMsg::AddChild { addr: all_addr },
+            ),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("would create a cycle"));
+}
+
+#[test]
+fn test_max_depth() {
+    let mut app = App::default();
+    let (all_addr, _, any_addr, _) = setup_tree(&mut app);
+    let creator = Addr::unchecked(CREATOR);
+
+    // The tree under satisfies-all is three levels deep
+    let all_id = app.store_code(satisfies_all());
+    let err = app
+        .instantiate_contract(
+            all_id,
+            creator.clone(),
+            &InstantiateMsg {
+                owner: creator.clone(),
+                parent: creator.clone(),
+                children: vec![all_addr.clone()],
+                max_depth: Some(3),
+            },
+            &[],
+            "Satisfies All",
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("deeper than the maximum of 3"));
+
+    let shallow_addr = app
+        .instantiate_contract(
+            all_id,
+            creator.clone(),
+            &InstantiateMsg {
+                owner: creator.clone(),
+                parent: creator.clone(),
+                children: vec![any_addr],
+                max_depth: Some(3),
+            },
+            &[],
+            "Satisfies All",
+            None,
+        )
+        .unwrap();
+    let err = app
+        .execute_contract(
+            creator.clone(),
+            shallow_addr,
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddChild {
+                addr: all_addr.clone(),
+            }),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("deeper than the maximum of 3"));
+
+    app.instantiate_contract(
+        all_id,
+        creator.clone(),
+        &InstantiateMsg {
+            owner: creator.clone(),
+            parent: creator,
+            children: vec![all_addr],
+            max_depth: Some(4),
+        },
+        &[],
+        "Satisfies All",
+        None,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_batch_matches_single_queries() {
+    let mut app = App::default();
+    let (all_addr, _, _, _) = setup_tree(&mut app);
+
+    let bank_msgs: Vec<CosmosMsg> = vec![BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1015, "earth"),
+    }
+    .into()];
+    let staking_msgs: Vec<CosmosMsg> = vec![StakingMsg::Delegate {
+        validator: "validator".to_string(),
+        amount: coin(1, "earth"),
+    }
+    .into()];
+    let requests = vec![
+        AuthorizationRequest {
+            msgs: bank_msgs.clone(),
+            context: Addr::unchecked(MEMBER).into(),
+        },
+        AuthorizationRequest {
+            msgs: staking_msgs,
+            context: Addr::unchecked(MEMBER).into(),
+        },
+        AuthorizationRequest {
+            msgs: bank_msgs,
+            context: Addr::unchecked("stranger").into(),
+        },
+    ];
+
+    let batch: IsAuthorizedBatchResponse = app
+        .wrap()
+        .query_wasm_smart(
+            all_addr.clone(),
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorizedBatch {
+                requests: requests.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(batch.responses.len(), 3);
+    assert!(batch.responses[0].authorized);
+    assert!(!batch.responses[1].authorized);
+    assert!(!batch.responses[2].authorized);
+
+    for (request, response) in requests.into_iter().zip(batch.responses) {
+        let single: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                all_addr.clone(),
+                &AuthoriazationQueryMsg::<Empty>::IsAuthorized {
+                    msgs: request.msgs,
+                    context: request.context,
+                },
+            )
+            .unwrap();
+        assert_eq!(single, response);
+    }
+}
+
+#[test]
+fn test_per_message_verdicts() {
+    let mut app = App::default();
+    let (all_addr, _, any_addr, _) = setup_tree(&mut app);
+
+    let msgs: Vec<CosmosMsg> = vec![
+        BankMsg::Send {
+            to_address: "you".to_string(),
+            amount: coins(1015, "earth"),
+        }
+        .into(),
+        StakingMsg::Delegate {
+            validator: "validator".to_string(),
+            amount: coin(1, "earth"),
+        }
+        .into(),
+    ];
+    let response: IsAuthorizedPerMessageResponse = app
+        .wrap()
+        .query_wasm_smart(
+            all_addr,
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorizedPerMessage {
+                msgs,
+                context: Addr::unchecked(MEMBER).into(),
+            },
+        )
+        .unwrap();
+    assert_eq!(response.responses.len(), 2);
+    assert!(response.responses[0].authorized);
+    assert!(!response.responses[1].authorized);
+    assert!(response.responses[1]
+        .reason
+        .as_ref()
+        .unwrap()
+        .contains(any_addr.as_str()));
+}
+
+/// Instantiates all[short_verdicts]
+fn setup_short_verdicts(app: &mut App) -> Addr {
+    let creator = Addr::unchecked(CREATOR);
+    let short_id = app.store_code(short_verdicts());
+    let short_addr = app
+        .instantiate_contract(short_id, creator.clone(), &Empty {}, &[], "Short", None)
+        .unwrap();
+    let all_id = app.store_code(satisfies_all());
+    app.instantiate_contract(
+        all_id,
+        creator.clone(),
+        &InstantiateMsg {
+            owner: creator.clone(),
+            parent: creator,
+            children: vec![short_addr],
+            max_depth: None,
+        },
+        &[],
+        "Satisfies All",
+        None,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_missing_batch_verdicts_are_rejected() {
+    let mut app = App::default();
+    let all_addr = setup_short_verdicts(&mut app);
+
+    let requests = vec![
+        AuthorizationRequest {
+            msgs: vec![],
+            context: Addr::unchecked(MEMBER).into(),
+        };
+        2
+    ];
+    let batch: IsAuthorizedBatchResponse = app
+        .wrap()
+        .query_wasm_smart(
+            all_addr,
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorizedBatch { requests },
+        )
+        .unwrap();
+    assert_eq!(batch.responses.len(), 2);
+    for response in batch.responses {
+        assert!(!response.authorized);
+        assert!(response
+            .reason
+            .unwrap()
+            .contains("Expected 2 verdicts but got 0"));
+    }
+}
+
+#[test]
+fn test_missing_per_message_verdicts_are_rejected() {
+    let mut app = App::default();
+    let all_addr = setup_short_verdicts(&mut app);
+
+    let msgs: Vec<CosmosMsg> = vec![
+        BankMsg::Send {
+            to_address: "you".to_string(),
+            amount: coins(1015, "earth"),
+        }
+        .into(),
+        StakingMsg::Delegate {
+            validator: "validator".to_string(),
+            amount: coin(1, "earth"),
+        }
+        .into(),
+    ];
+    let response: IsAuthorizedPerMessageResponse = app
+        .wrap()
+        .query_wasm_smart(
+            all_addr,
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorizedPerMessage {
+                msgs,
+                context: Addr::unchecked(MEMBER).into(),
+            },
+        )
+        .unwrap();
+    assert_eq!(response.responses.len(), 2);
+    assert!(response.responses.iter().all(|r| !r.authorized));
+}
+
+/// Instantiates all[child]
+fn instantiate_parent_of(app: &mut App, child: &Addr) -> Addr {
+    let creator = Addr::unchecked(CREATOR);
+    let all_id = app.store_code(satisfies_all());
+    app.instantiate_contract(
+        all_id,
+        creator.clone(),
+        &InstantiateMsg {
+            owner: creator.clone(),
+            parent: creator,
+            children: vec![child.clone()],
+            max_depth: None,
+        },
+        &[],
+        "Satisfies All",
+        None,
+    )
+    .unwrap()
+}
+
+/// Instantiates a message filter that allows MEMBER to send bank messages
+/// within a budget or a number of uses
+fn instantiate_limited_filter(
+    app: &mut App,
+    budget: Option<message_filter::state::Budget>,
+    max_uses: Option<u64>,
+) -> Addr {
+    let creator = Addr::unchecked(CREATOR);
+    let filter_id = app.store_code(message_filter());
+    let filter_addr = app
+        .instantiate_contract(
+            filter_id,
+            creator.clone(),
+            &message_filter::msg::InstantiateMsg {
+                owner: creator.clone(),
+                kind: message_filter::state::Kind::Allow {},
+                decode_binaries: false,
+                conflict_strategy: Default::default(),
+                evaluation_mode: Default::default(),
+            },
+            &[],
+            "Message Filter",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        creator,
+        filter_addr.clone(),
+        &AuthoriazationExecuteMsg::<message_filter::msg::ExecuteMsg>::Extension(
+            message_filter::msg::ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked(MEMBER),
+                msg: r#"{"bank": {}}"#.to_string(),
+                effect: None,
+                priority: None,
+                budget,
+                max_uses,
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+    filter_addr
+}
+
+fn set_filter_updaters(app: &mut App, filter_addr: &Addr, updaters: Vec<String>) {
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        filter_addr.clone(),
+        &AuthoriazationExecuteMsg::<message_filter::msg::ExecuteMsg>::Extension(
+            message_filter::msg::ExecuteMsg::SetUpdaters { updaters },
+        ),
+        &[],
+    )
+    .unwrap();
+}
+
+fn bank_send(amount: u128) -> CosmosMsg {
+    BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(amount, "ujuno"),
+    }
+    .into()
+}
+
+fn is_member_authorized(app: &App, addr: &Addr, msgs: Vec<CosmosMsg>) -> bool {
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query_wasm_smart(
+            addr,
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorized {
+                msgs,
+                context: Addr::unchecked(MEMBER).into(),
+            },
+        )
+        .unwrap();
+    response.authorized
+}
+
+/// Sends an update on behalf of MEMBER, returning the cause of the error if
+/// it fails
+fn update_for_member(
+    app: &mut App,
+    sender: &str,
+    addr: &Addr,
+    msgs: Vec<CosmosMsg>,
+) -> Result<(), String> {
+    app.execute_contract(
+        Addr::unchecked(sender),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<Empty>::UpdateExecutedAuthorizationState {
+            msgs,
+            context: Addr::unchecked(MEMBER).into(),
+        },
+        &[],
+    )
+    .map(|_| ())
+    .map_err(|e| e.root_cause().to_string())
+}
+
+#[test]
+fn test_forged_updates_cannot_spend_budgets() {
+    let mut app = App::default();
+    let filter_addr = instantiate_limited_filter(
+        &mut app,
+        Some(message_filter::state::Budget {
+            limits: coins(100, "ujuno"),
+            period: None,
+        }),
+        None,
+    );
+    let all_addr = instantiate_parent_of(&mut app, &filter_addr);
+    set_filter_updaters(&mut app, &filter_addr, vec![all_addr.to_string()]);
+
+    // A third party can't spend MEMBER's budget through satisfies-all
+    let err = update_for_member(&mut app, "stranger", &all_addr, vec![bank_send(100)]).unwrap_err();
+    assert!(err.contains("cannot specify a sender other than the caller"));
+    assert!(is_member_authorized(&app, &all_addr, vec![bank_send(100)]));
+
+    // MEMBER's own updates are forwarded
+    update_for_member(&mut app, MEMBER, &all_addr, vec![bank_send(60)]).unwrap();
+    assert!(!is_member_authorized(&app, &all_addr, vec![bank_send(60)]));
+    assert!(is_member_authorized(&app, &all_addr, vec![bank_send(40)]));
+}
+
+#[test]
+fn test_forged_updates_cannot_use_up_matchers() {
+    let mut app = App::default();
+    let filter_addr = instantiate_limited_filter(&mut app, None, Some(1));
+    let all_addr = instantiate_parent_of(&mut app, &filter_addr);
+    set_filter_updaters(&mut app, &filter_addr, vec![all_addr.to_string()]);
+
+    // A third party can't use up MEMBER's matcher through satisfies-all
+    let err = update_for_member(&mut app, "stranger", &all_addr, vec![bank_send(1)]).unwrap_err();
+    assert!(err.contains("cannot specify a sender other than the caller"));
+    assert!(is_member_authorized(&app, &all_addr, vec![bank_send(1)]));
+
+    update_for_member(&mut app, MEMBER, &all_addr, vec![bank_send(1)]).unwrap();
+    assert!(!is_member_authorized(&app, &all_addr, vec![bank_send(1)]));
+}
+
+#[test]
+fn test_forged_updates_cannot_use_up_whitelist_entries() {
+    let mut app = App::default();
+    let creator = Addr::unchecked(CREATOR);
+    let whitelist_id = app.store_code(whitelist());
+    let whitelist_addr = app
+        .instantiate_contract(
+            whitelist_id,
+            creator.clone(),
+            &whitelist::msg::InstantiateMsg {
+                owner: creator.clone(),
+            },
+            &[],
+            "Whitelist",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        creator.clone(),
+        whitelist_addr.clone(),
+        &AuthoriazationExecuteMsg::<whitelist::msg::ExecuteMsg>::Extension(
+            whitelist::msg::ExecuteMsg::Allow {
+                addr: MEMBER.to_string(),
+                max_uses: Some(1),
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+    let all_addr = instantiate_parent_of(&mut app, &whitelist_addr);
+    app.execute_contract(
+        creator,
+        whitelist_addr,
+        &AuthoriazationExecuteMsg::<whitelist::msg::ExecuteMsg>::Extension(
+            whitelist::msg::ExecuteMsg::SetUpdaters {
+                updaters: vec![all_addr.to_string()],
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+
+    // A third party can't use up MEMBER's entry through satisfies-all
+    let err = update_for_member(&mut app, "stranger", &all_addr, vec![bank_send(1)]).unwrap_err();
+    assert!(err.contains("cannot specify a sender other than the caller"));
+    assert!(is_member_authorized(&app, &all_addr, vec![bank_send(1)]));
+
+    update_for_member(&mut app, MEMBER, &all_addr, vec![bank_send(1)]).unwrap();
+    assert!(!is_member_authorized(&app, &all_addr, vec![bank_send(1)]));
+}
+
+#[test]
+fn test_failure_policies() {
+    let mut app = App::default();
+    let (all_addr, _, any_addr, _) = setup_tree(&mut app);
+    let creator = Addr::unchecked(CREATOR);
+
+    // As in test_child_update_errors_are_handled, updating satisfies-any
+    // through satisfies-all fails.
+    let update = AuthoriazationExecuteMsg::<Empty>::UpdateExecutedAuthorizationState {
+        msgs: vec![],
+        context: Addr::unchecked(MEMBER).into(),
+    };
+    let set_policy = |app: &mut App, policy: FailurePolicy| {
+        app.execute_contract(
+            creator.clone(),
+            all_addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::SetFailurePolicy {
+                child: any_addr.clone(),
+                policy,
+            }),
+            &[],
+        )
+        .unwrap();
+    };
+    let failed_updates = |app: &App| -> FailedUpdatesResponse {
+        app.wrap()
+            .query_wasm_smart(
+                all_addr.clone(),
+                &AuthoriazationQueryMsg::<Empty>::FailedUpdates {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap()
+    };
+
+    // Strict failures revert the whole execution
+    set_policy(&mut app, FailurePolicy::Strict {});
+    app.execute_contract(creator.clone(), all_addr.clone(), &update, &[])
+        .unwrap_err();
+
+    // Failures are recorded so that they can be retried
+    set_policy(&mut app, FailurePolicy::RecordForRetry {});
+    app.execute_contract(creator.clone(), all_addr.clone(), &update, &[])
+        .unwrap();
+    let failed = failed_updates(&app).updates;
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].child, any_addr);
+    assert!(!failed[0].error.is_empty());
+
+    // Only the owner can retry
+    let err = app
+        .execute_contract(
+            Addr::unchecked(MEMBER),
+            all_addr.clone(),
+            &AuthoriazationExecuteMsg::<Empty>::RetryFailedUpdate { id: failed[0].id },
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("is not the owner of this contract"));
+    assert_eq!(failed_updates(&app).updates, failed);
+
+    // Retrying an update that fails again records it under a new id
+    app.execute_contract(
+        creator.clone(),
+        all_addr.clone(),
+        &AuthoriazationExecuteMsg::<Empty>::RetryFailedUpdate { id: failed[0].id },
+        &[],
+    )
+    .unwrap();
+    let retried = failed_updates(&app).updates;
+    assert_eq!(retried.len(), 1);
+    assert_ne!(retried[0].id, failed[0].id);
+    assert_eq!(retried[0].msg, failed[0].msg);
+
+    // Only children can have a policy
+    app.execute_contract(
+        creator,
+        all_addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::SetFailurePolicy {
+            child: Addr::unchecked(MEMBER),
+            policy: FailurePolicy::Strict {},
+        }),
+        &[],
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn test_ownership() {
+    let mut app = App::default();
+    let (_, whitelist_addr, _, filter_addr) = setup_tree(&mut app);
+
+    // Every authorization exposes its ownership in the same way
+    for addr in [&whitelist_addr, &filter_addr] {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<Empty>::UpdateOwnership(OwnershipAction::ProposeOwner {
+                new_owner: MEMBER.to_string(),
+            }),
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(MEMBER),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<Empty>::UpdateOwnership(
+                OwnershipAction::AcceptOwnership {},
+            ),
+            &[],
+        )
+        .unwrap();
+        let ownership: Ownership = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &AuthoriazationQueryMsg::<Empty>::Ownership {})
+            .unwrap();
+        assert_eq!(ownership.owner, Some(Addr::unchecked(MEMBER)));
+    }
+
+    // The new owner manages the whitelist
+    app.execute_contract(
+        Addr::unchecked(MEMBER),
+        whitelist_addr,
+        &AuthoriazationExecuteMsg::<whitelist::msg::ExecuteMsg>::Extension(
+            whitelist::msg::ExecuteMsg::Allow {
+                addr: "someone".to_string(),
+                max_uses: None,
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+}
+
+/// Stores the state as it was stored before 0.2.0, when the owner was called
+/// the admin
+fn instantiate_v0_1(name: &str, deps: DepsMut, msg: InstantiateMsg) -> Result<Response, StdError> {
+    cw2::set_contract_version(deps.storage, name, "0.1.0")?;
+    Item::new("admin").save(deps.storage, &msg.owner)?;
+    Item::new("parent").save(deps.storage, &msg.parent)?;
+    for child in msg.children {
+        Map::new("children").save(deps.storage, child, &Empty {})?;
+    }
+    Ok(Response::default())
+}
+
+fn satisfies_all_v0_1() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        |deps: DepsMut, _env: Env, _info: MessageInfo, msg: InstantiateMsg| {
+            instantiate_v0_1("crates.io:satisfies-all", deps, msg)
+        },
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn satisfies_any_v0_1() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        satisfies_any::contract::execute,
+        |deps: DepsMut, _env: Env, _info: MessageInfo, msg: InstantiateMsg| {
+            instantiate_v0_1("crates.io:satisfies-any", deps, msg)
+        },
+        satisfies_any::contract::query,
+    );
+    Box::new(contract)
+}
+
+#[test]
+fn test_migrate_from_v0_1() {
+    let mut app = App::default();
+    let (_, whitelist_addr, _, _) = setup_tree(&mut app);
+    let creator = Addr::unchecked(CREATOR);
+
+    let migrations = [
+        (satisfies_all_v0_1(), satisfies_all()),
+        (satisfies_any_v0_1(), satisfies_any()),
+    ];
+    for (legacy, current) in migrations {
+        let legacy_id = app.store_code(legacy);
+        let addr = app
+            .instantiate_contract(
+                legacy_id,
+                creator.clone(),
+                &InstantiateMsg {
+                    owner: creator.clone(),
+                    parent: creator.clone(),
+                    children: vec![whitelist_addr.clone()],
+                    max_depth: None,
+                },
+                &[],
+                "Legacy",
+                Some(CREATOR.to_string()),
+            )
+            .unwrap();
+
+        let code_id = app.store_code(current);
+        app.migrate_contract(creator.clone(), addr.clone(), &MigrateMsg {}, code_id)
+            .unwrap();
+
+        // The admin became the owner and the children were kept
+        let info: InfoResponse = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &AuthoriazationQueryMsg::<Empty>::Info {})
+            .unwrap();
+        assert_eq!(info.children, vec![whitelist_addr.clone()]);
+        let ownership: Ownership = app
+            .wrap()
+            .query_wasm_smart(addr.clone(), &AuthoriazationQueryMsg::<Empty>::Ownership {})
+            .unwrap();
+        assert_eq!(ownership.owner, Some(creator.clone()));
+        app.execute_contract(
+            creator.clone(),
+            addr,
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::RemoveChild {
+                addr: whitelist_addr.clone(),
+            }),
+            &[],
+        )
+        .unwrap();
+    }
+}
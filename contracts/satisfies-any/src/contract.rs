@@ -1,53 +1,171 @@
-#[cfg(not(feature = "library"))]
-use cosmwasm_std::entry_point;
+use std::marker::PhantomData;
 
 use cosmwasm_std::{
-    Addr, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order, Reply, Response,
-    StdError, StdResult,
+    Addr, CosmosMsg, CustomMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdError,
+    StdResult,
 };
-use cw2::set_contract_version;
+use cw_authorizations::error::{EmptyError, QUERY_FAILED_CODE, UNAUTHORIZED_CODE};
+use cw_authorizations::migration::Version;
 use cw_authorizations::msg::{
-    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, IsAuthorizedResponse,
+    AuthoriazationQueryMsg, AuthorizationContext, AuthorizationRequest, FailurePolicy,
+    IsAuthorizedBatchResponse, IsAuthorizedPerMessageResponse, IsAuthorizedResponse,
 };
+use cw_authorizations::validation::{validate_child, validate_edge, DEFAULT_MAX_DEPTH};
 use cw_authorizations::{Authorization, AuthorizationError};
+use cw_authorizations_derive::authorization_contract;
 
-use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::migrations::migrate_from_v0_1;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg};
 use crate::state::ProxyState;
 
-const CONTRACT_NAME: &str = "crates.io:satisfies-any";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-
-pub struct SatisfiesAnyContract {
+/// `C` is the custom message type of the chain. Messages are forwarded to the
+/// children as `CosmosMsg<C>`.
+pub struct SatisfiesAnyContract<C = Empty> {
     state: ProxyState,
+    custom: PhantomData<C>,
 }
 
-impl SatisfiesAnyContract {
+impl<C> SatisfiesAnyContract<C> {
     pub fn instantiate(
         &self,
         deps: DepsMut,
         msg: InstantiateMsg,
     ) -> Result<(), AuthorizationError> {
-        self.state.admin.save(deps.storage, &msg.admin)?;
+        self.state.ownership.initialize(deps.storage, msg.owner)?;
         self.state.parent.save(deps.storage, &msg.parent)?;
+        let max_depth = msg.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+        self.state.max_depth.save(deps.storage, &max_depth)?;
         for child in msg.children {
+            let child = validate_child(deps.as_ref(), &child)?;
+            validate_edge(deps.as_ref(), None, &child, max_depth)?;
             self.state.children.save(deps.storage, child, &Empty {})?;
         }
         Ok(())
     }
+
+    pub fn migrate(
+        &self,
+        deps: DepsMut,
+        _msg: MigrateMsg,
+        from_version: &Version,
+    ) -> Result<(), AuthorizationError> {
+        if *from_version < Version::new(0, 2, 0) {
+            migrate_from_v0_1(deps.storage, &self.state)?;
+        }
+        Ok(())
+    }
+
+    /// Combines the verdicts of all children for `count` independent checks.
+    /// `query` asks a child about the checks at the given indices and returns
+    /// one verdict per index. A check is authorized if any child authorizes
+    /// it.
+    fn combine_children<Q>(
+        &self,
+        deps: Deps,
+        count: usize,
+        query: Q,
+    ) -> Result<Vec<IsAuthorizedResponse>, AuthorizationError>
+    where
+        Q: Fn(&Addr, &[usize]) -> StdResult<Vec<IsAuthorizedResponse>>,
+    {
+        let children: Vec<(Addr, Empty)> = self
+            .state
+            .children
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<Result<_, StdError>>()?;
+
+        if children.is_empty() {
+            return Ok(vec![
+                IsAuthorizedResponse::unauthorized(
+                    UNAUTHORIZED_CODE,
+                    Some("There are no sub authorizations".to_string())
+                );
+                count
+            ]);
+        }
+
+        let mut authorized = vec![false; count];
+        let mut rejections: Vec<Vec<String>> = vec![vec![]; count];
+        for child in children.into_iter().map(|c| c.0) {
+            // Only the checks that no previous child authorized need to be
+            // sent
+            let pending: Vec<usize> = (0..count).filter(|&i| !authorized[i]).collect();
+            if pending.is_empty() {
+                break;
+            }
+
+            // A child that doesn't return one verdict per check is treated
+            // like a failed query so that its verdicts aren't misattributed
+            let child_responses = query(&child, &pending)
+                .and_then(|responses| {
+                    if responses.len() == pending.len() {
+                        Ok(responses)
+                    } else {
+                        Err(StdError::generic_err(format!(
+                            "Expected {} verdicts but got {}",
+                            pending.len(),
+                            responses.len()
+                        )))
+                    }
+                })
+                .unwrap_or_else(|e| {
+                    vec![
+                        IsAuthorizedResponse::unauthorized(QUERY_FAILED_CODE, Some(e.to_string()));
+                        pending.len()
+                    ]
+                });
+            for (i, response) in pending.into_iter().zip(child_responses) {
+                if response.authorized {
+                    authorized[i] = true;
+                } else {
+                    rejections[i].push(format!(
+                        "{}: {}",
+                        child,
+                        response.reason.or(response.code).unwrap_or_default()
+                    ));
+                }
+            }
+        }
+
+        Ok(authorized
+            .into_iter()
+            .zip(rejections)
+            .map(|(authorized, rejections)| {
+                if authorized {
+                    IsAuthorizedResponse::authorized()
+                } else {
+                    IsAuthorizedResponse::unauthorized(
+                        UNAUTHORIZED_CODE,
+                        Some(format!(
+                            "No sub authorization passed ({})",
+                            rejections.join("; ")
+                        )),
+                    )
+                }
+            })
+            .collect())
+    }
 }
 
-impl Authorization<ExecuteMsg> for SatisfiesAnyContract {
+#[authorization_contract(instantiate = InstantiateMsg, execute = ExecuteMsg, migrate = MigrateMsg)]
+impl<C: CustomMsg> Authorization<ExecuteMsg, Empty, EmptyError, C> for SatisfiesAnyContract<C> {
     fn new() -> Self {
         SatisfiesAnyContract {
             state: ProxyState::new(),
+            custom: PhantomData,
         }
     }
 
+    fn contract_type(&self) -> String {
+        "satisfies_any".to_string()
+    }
+
     fn is_authorized(
         &self,
         deps: Deps,
-        msgs: &Vec<CosmosMsg>,
-        sender: &Addr,
+        _env: &Env,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
     ) -> Result<bool, AuthorizationError> {
         // Right now this defaults to an *or*. We could update the contract to
         // support a custom allow/reject behaviour (similarly to how it's done in
@@ -59,21 +177,94 @@ impl Authorization<ExecuteMsg> for SatisfiesAnyContract {
             .collect::<Result<_, StdError>>()?;
 
         if children.is_empty() {
-            return Ok(false);
+            return Err(AuthorizationError::Unauthorized {
+                reason: Some("There are no sub authorizations".to_string()),
+            });
         }
 
-        Ok(children.into_iter().map(|c| c.0).any(|a| {
-            deps.querier
+        let mut rejections = vec![];
+        for child in children.into_iter().map(|c| c.0) {
+            let response = deps
+                .querier
                 .query_wasm_smart(
-                    a.clone(),
-                    &AuthoriazationQueryMsg::IsAuthorized::<Empty> {
-                        msgs: msgs.clone(),
-                        sender: sender.clone(),
+                    child.clone(),
+                    &AuthoriazationQueryMsg::IsAuthorized::<Empty, C> {
+                        msgs: msgs.to_vec(),
+                        context: context.clone(),
                     },
                 )
-                .unwrap_or(IsAuthorizedResponse { authorized: false })
-                .authorized
-        }))
+                .unwrap_or_else(|e| {
+                    IsAuthorizedResponse::unauthorized(QUERY_FAILED_CODE, Some(e.to_string()))
+                });
+            if response.authorized {
+                return Ok(true);
+            }
+            rejections.push(format!(
+                "{}: {}",
+                child,
+                response.reason.or(response.code).unwrap_or_default()
+            ));
+        }
+
+        Err(AuthorizationError::Unauthorized {
+            reason: Some(format!(
+                "No sub authorization passed ({})",
+                rejections.join("; ")
+            )),
+        })
+    }
+
+    fn is_authorized_batch(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        requests: &[AuthorizationRequest<C>],
+    ) -> Result<Vec<IsAuthorizedResponse>, AuthorizationError> {
+        self.combine_children(deps, requests.len(), |child, pending| {
+            deps.querier
+                .query_wasm_smart::<IsAuthorizedBatchResponse>(
+                    child,
+                    &AuthoriazationQueryMsg::IsAuthorizedBatch::<Empty, C> {
+                        requests: pending.iter().map(|&i| requests[i].clone()).collect(),
+                    },
+                )
+                .map(|r| r.responses)
+        })
+    }
+
+    /// Each message is authorized if any child authorizes it, so every
+    /// message may be authorized individually even if no single child
+    /// authorizes all of them together.
+    fn is_authorized_per_message(
+        &self,
+        deps: Deps,
+        _env: &Env,
+        msgs: &[CosmosMsg<C>],
+        context: &AuthorizationContext,
+    ) -> Result<Vec<IsAuthorizedResponse>, AuthorizationError> {
+        self.combine_children(deps, msgs.len(), |child, pending| {
+            deps.querier
+                .query_wasm_smart::<IsAuthorizedPerMessageResponse>(
+                    child,
+                    &AuthoriazationQueryMsg::IsAuthorizedPerMessage::<Empty, C> {
+                        msgs: pending.iter().map(|&i| msgs[i].clone()).collect(),
+                        context: context.clone(),
+                    },
+                )
+                .map(|r| r.responses)
+        })
+    }
+
+    fn failure_policy(
+        &self,
+        deps: Deps,
+        child: &Addr,
+    ) -> Result<FailurePolicy, AuthorizationError> {
+        Ok(self
+            .state
+            .policies
+            .may_load(deps.storage, child.clone())?
+            .unwrap_or_default())
     }
 
     fn get_sub_authorizations(&self, deps: Deps) -> Result<Vec<Addr>, AuthorizationError> {
@@ -88,94 +279,61 @@ impl Authorization<ExecuteMsg> for SatisfiesAnyContract {
 
     fn update_authorization_state(
         &self,
-        deps: Deps,
-        msgs: &Vec<CosmosMsg>,
-        sender: &Addr,
+        deps: DepsMut,
+        env: &Env,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
         real_sender: &Addr,
     ) -> Result<Response, AuthorizationError> {
         let parent = self.state.parent.load(deps.storage)?;
-        if sender != real_sender && real_sender != &parent {
+        if &context.sender != real_sender && real_sender != &parent {
             return Err(AuthorizationError::Unauthorized {
-            //reason: Some("Auth updates that aren't triggered by a parent contract cannot specify a sender other than the caller ".to_string()),
+                reason: Some("Auth updates that aren't triggered by a parent contract cannot specify a sender other than the caller".to_string()),
             });
         }
 
         // If at least one authorization module authorized this message, we send the
         // Authorize execute message to all the authorizations so that they can update their
-        // state if needed.
-        if self.is_authorized(deps, &msgs, &sender)? {
-            let sub_msgs = self.generate_child_update_msgs(deps, msgs, sender)?;
-            Ok(Response::default().add_submessages(sub_msgs))
-        } else {
-            Err(AuthorizationError::Unauthorized {
-                //reason: Some("No sub authorization passed".to_string()),
-            })
-        }
+        // state if needed. is_authorized returns an error when none did.
+        self.is_authorized(deps.as_ref(), env, msgs, context)?;
+        let sub_msgs = self.generate_child_update_msgs(deps, msgs, context)?;
+        Ok(Response::default().add_submessages(sub_msgs))
     }
 
     fn execute_extension(
         &self,
         deps: DepsMut,
-        _env: Env,
+        env: Env,
         info: MessageInfo,
         msg: ExecuteMsg,
     ) -> Result<Response, AuthorizationError> {
-        if info.sender != self.state.admin.load(deps.storage)? {
-            return Err(AuthorizationError::Unauthorized {
-                //reason: Some("Only the parent can execute on this contract".to_string()),
-            }
-            .into());
-        }
+        self.state
+            .ownership
+            .assert_owner(deps.storage, &info.sender)?;
         match msg {
             ExecuteMsg::AddChild { addr } => {
+                let addr = validate_child(deps.as_ref(), &addr)?;
+                let max_depth = self
+                    .state
+                    .max_depth
+                    .may_load(deps.storage)?
+                    .unwrap_or(DEFAULT_MAX_DEPTH);
+                validate_edge(deps.as_ref(), Some(&env.contract.address), &addr, max_depth)?;
                 self.state.children.save(deps.storage, addr, &Empty {})?;
                 Ok(Response::default().add_attribute("action", "allow"))
             }
             ExecuteMsg::RemoveChild { addr } => {
-                self.state.children.remove(deps.storage, addr);
+                self.state.children.remove(deps.storage, addr.clone());
+                self.state.policies.remove(deps.storage, addr);
                 Ok(Response::default().add_attribute("action", "remove"))
             }
+            ExecuteMsg::SetFailurePolicy { child, policy } => {
+                if !self.state.children.has(deps.storage, child.clone()) {
+                    return Err(AuthorizationError::Std(StdError::not_found("child")));
+                }
+                self.state.policies.save(deps.storage, child, &policy)?;
+                Ok(Response::default().add_attribute("action", "set_failure_policy"))
+            }
         }
     }
 }
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    msg: InstantiateMsg,
-) -> Result<Response, AuthorizationError> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    SatisfiesAnyContract::new().instantiate(deps, msg)?;
-    Ok(Response::default().add_attribute("action", "instantiate"))
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: AuthoriazationExecuteMsg<ExecuteMsg>,
-) -> Result<Response, AuthorizationError> {
-    SatisfiesAnyContract::new().execute(deps, env, info, msg)
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: AuthoriazationQueryMsg) -> StdResult<Binary> {
-    SatisfiesAnyContract::new().query(deps, env, msg)
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, AuthorizationError> {
-    let contract = SatisfiesAnyContract::new();
-    match msg.id {
-        // Update reply errors are always ignored.
-        id if id == contract.get_update_reply_id() => contract.sub_message_reply(msg),
-        id => Err(AuthorizationError::Std(
-            cosmwasm_std::StdError::GenericErr {
-                msg: format!("Unknown reply id: {}", id),
-            },
-        )),
-    }
-}
@@ -1,6 +1,10 @@
 pub mod contract;
 mod error;
+mod migrations;
 pub mod msg;
 pub mod state;
 
+#[cfg(test)]
+mod tests;
+
 pub use crate::error::ContractError;
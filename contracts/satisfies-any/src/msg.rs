@@ -1,17 +1,31 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{Addr, CustomMsg};
+use cw_authorizations::msg::FailurePolicy;
 
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub admin: Addr,
+    pub owner: Addr,
     pub parent: Addr,
     pub children: Vec<Addr>,
+    /// The maximum depth of the subtree rooted at this contract, checked when
+    /// children are added. Levels above this contract aren't counted. Defaults
+    /// to `DEFAULT_MAX_DEPTH`
+    pub max_depth: Option<u32>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    AddChild { addr: Addr },
-    RemoveChild { addr: Addr },
+    AddChild {
+        addr: Addr,
+    },
+    RemoveChild {
+        addr: Addr,
+    },
+    /// Sets what to do when updating the state of a child fails
+    SetFailurePolicy {
+        child: Addr,
+        policy: FailurePolicy,
+    },
 }
 
 impl CustomMsg for ExecuteMsg {}
@@ -20,3 +34,6 @@ impl CustomMsg for ExecuteMsg {}
 pub enum QueryMsg {
     GetAuthorizations {},
 }
+
+#[cw_serde]
+pub struct MigrateMsg {}
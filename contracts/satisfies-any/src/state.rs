@@ -1,18 +1,32 @@
 use cosmwasm_std::Addr;
+use cw_authorizations::msg::FailurePolicy;
+use cw_authorizations::ownership::OwnershipState;
 use cw_storage_plus::{Item, Map};
 
 pub struct ProxyState {
-    pub admin: Item<'static, Addr>,
+    pub ownership: OwnershipState,
     pub parent: Item<'static, Addr>,
     pub children: Map<'static, Addr, cosmwasm_std::Empty>,
+    pub max_depth: Item<'static, u32>,
+    /// The failure policy of each child. Children without one use the
+    /// default (best effort)
+    pub policies: Map<'static, Addr, FailurePolicy>,
 }
 
 impl ProxyState {
     pub const fn new() -> Self {
         ProxyState {
-            admin: Item::new("admin"),
+            ownership: OwnershipState::new(),
             parent: Item::new("parent"),
             children: Map::new("children"),
+            max_depth: Item::new("max_depth"),
+            policies: Map::new("policies"),
         }
     }
 }
+
+impl Default for ProxyState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
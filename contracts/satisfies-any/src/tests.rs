@@ -0,0 +1,538 @@
+use cosmwasm_std::{
+    coin, coins, to_binary, Addr, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Empty, Env,
+    MessageInfo, Response, StakingMsg, StdError, StdResult,
+};
+use cw_authorizations::interface::INTERFACE_VERSION;
+use cw_authorizations::msg::{
+    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, AuthorizationRequest, InfoResponse,
+    IsAuthorizedBatchResponse, IsAuthorizedPerMessageResponse, IsAuthorizedResponse,
+};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+const CREATOR: &str = "creator";
+const MEMBER: &str = "member";
+const STRANGER: &str = "stranger";
+
+fn satisfies_any() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_reply(crate::contract::reply)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn message_filter() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        message_filter::contract::execute,
+        message_filter::contract::instantiate,
+        message_filter::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn whitelist() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        whitelist::contract::execute,
+        whitelist::contract::instantiate,
+        whitelist::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn bank_msg() -> CosmosMsg {
+    BankMsg::Send {
+        to_address: "you".to_string(),
+        amount: coins(1015, "earth"),
+    }
+    .into()
+}
+
+fn staking_msg() -> CosmosMsg {
+    StakingMsg::Delegate {
+        validator: "validator".to_string(),
+        amount: coin(1, "earth"),
+    }
+    .into()
+}
+
+fn short_verdicts_query(
+    _deps: Deps,
+    _env: Env,
+    msg: AuthoriazationQueryMsg<Empty>,
+) -> StdResult<Binary> {
+    match msg {
+        AuthoriazationQueryMsg::Info {} => to_binary(&InfoResponse {
+            contract_type: "short_verdicts".to_string(),
+            contract_name: "short-verdicts".to_string(),
+            contract_version: "0.1.0".to_string(),
+            interface_version: INTERFACE_VERSION.to_string(),
+            children: vec![],
+            stateful: false,
+        }),
+        AuthoriazationQueryMsg::IsAuthorized { .. } => {
+            to_binary(&IsAuthorizedResponse::authorized())
+        }
+        AuthoriazationQueryMsg::IsAuthorizedBatch { .. } => to_binary(&IsAuthorizedBatchResponse {
+            responses: vec![IsAuthorizedResponse::authorized()],
+        }),
+        AuthoriazationQueryMsg::IsAuthorizedPerMessage { .. } => {
+            to_binary(&IsAuthorizedPerMessageResponse {
+                responses: vec![IsAuthorizedResponse::authorized()],
+            })
+        }
+        _ => Err(StdError::generic_err("unsupported")),
+    }
+}
+
+/// A misbehaving child that returns a single authorized verdict for batch and
+/// per-message queries, however many checks it was asked about
+fn short_verdicts() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::default())
+        },
+        short_verdicts_query,
+    );
+    Box::new(contract)
+}
+
+/// Instantiates a message filter that allows MEMBER to send `msg`
+fn instantiate_filter(app: &mut App, msg: &str) -> Addr {
+    let creator = Addr::unchecked(CREATOR);
+    let filter_id = app.store_code(message_filter());
+    let filter_addr = app
+        .instantiate_contract(
+            filter_id,
+            creator.clone(),
+            &message_filter::msg::InstantiateMsg {
+                owner: creator.clone(),
+                kind: message_filter::state::Kind::Allow {},
+                decode_binaries: false,
+                conflict_strategy: Default::default(),
+                evaluation_mode: Default::default(),
+            },
+            &[],
+            "Message Filter",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        creator,
+        filter_addr.clone(),
+        &AuthoriazationExecuteMsg::<message_filter::msg::ExecuteMsg>::Extension(
+            message_filter::msg::ExecuteMsg::AddAuthorization {
+                addr: Addr::unchecked(MEMBER),
+                msg: msg.to_string(),
+                effect: None,
+                priority: None,
+                budget: None,
+                max_uses: None,
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+    filter_addr
+}
+
+fn instantiate_any(app: &mut App, children: Vec<Addr>, max_depth: Option<u32>) -> Addr {
+    let creator = Addr::unchecked(CREATOR);
+    let any_id = app.store_code(satisfies_any());
+    app.instantiate_contract(
+        any_id,
+        creator.clone(),
+        &InstantiateMsg {
+            owner: creator.clone(),
+            parent: creator,
+            children,
+            max_depth,
+        },
+        &[],
+        "Satisfies Any",
+        None,
+    )
+    .unwrap()
+}
+
+/// Builds any[filter(bank), filter(staking)] where each filter authorizes a
+/// different kind of message sent by MEMBER.
+fn setup_tree(app: &mut App) -> (Addr, Addr, Addr) {
+    let bank_filter = instantiate_filter(app, r#"{"bank": {}}"#);
+    let staking_filter = instantiate_filter(app, r#"{"staking": {}}"#);
+    let any_addr = instantiate_any(app, vec![bank_filter.clone(), staking_filter.clone()], None);
+    (any_addr, bank_filter, staking_filter)
+}
+
+#[test]
+fn test_incompatible_children_are_rejected() {
+    let mut app = App::default();
+    let (any_addr, _, _) = setup_tree(&mut app);
+    let creator = Addr::unchecked(CREATOR);
+
+    // MEMBER is not a contract, so it can't be an authorization
+    let err = app
+        .execute_contract(
+            creator.clone(),
+            any_addr,
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddChild {
+                addr: Addr::unchecked(MEMBER),
+            }),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("is not a compatible authorization"));
+
+    let any_id = app.store_code(satisfies_any());
+    let err = app
+        .instantiate_contract(
+            any_id,
+            creator.clone(),
+            &InstantiateMsg {
+                owner: creator.clone(),
+                parent: creator,
+                children: vec![Addr::unchecked(MEMBER)],
+                max_depth: None,
+            },
+            &[],
+            "Satisfies Any",
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("is not a compatible authorization"));
+}
+
+#[test]
+fn test_cycles_are_rejected() {
+    let mut app = App::default();
+    let (inner_addr, _, _) = setup_tree(&mut app);
+    let outer_addr = instantiate_any(&mut app, vec![inner_addr.clone()], None);
+    let creator = Addr::unchecked(CREATOR);
+
+    // A contract can't be its own child
+    let err = app
+        .execute_contract(
+            creator.clone(),
+            inner_addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddChild {
+                addr: inner_addr.clone(),
+            }),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("would create a cycle"));
+
+    // The outer contract is an ancestor of the inner one
+    let err = app
+        .execute_contract(
+            creator,
+            inner_addr,
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddChild {
+                addr: outer_addr,
+            }),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("would create a cycle"));
+}
+
+#[test]
+fn test_max_depth() {
+    let mut app = App::default();
+    let (inner_addr, bank_filter, _) = setup_tree(&mut app);
+    let creator = Addr::unchecked(CREATOR);
+
+    // The tree under inner_addr is two levels deep
+    let any_id = app.store_code(satisfies_any());
+    let err = app
+        .instantiate_contract(
+            any_id,
+            creator.clone(),
+            &InstantiateMsg {
+                owner: creator.clone(),
+                parent: creator.clone(),
+                children: vec![inner_addr.clone()],
+                max_depth: Some(2),
+            },
+            &[],
+            "Satisfies Any",
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("deeper than the maximum of 2"));
+
+    let shallow_addr = instantiate_any(&mut app, vec![bank_filter], Some(2));
+    let err = app
+        .execute_contract(
+            creator,
+            shallow_addr,
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::AddChild {
+                addr: inner_addr.clone(),
+            }),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("deeper than the maximum of 2"));
+
+    instantiate_any(&mut app, vec![inner_addr], Some(3));
+}
+
+#[test]
+fn test_batch_matches_single_queries() {
+    let mut app = App::default();
+    let (any_addr, _, _) = setup_tree(&mut app);
+
+    let requests = vec![
+        AuthorizationRequest {
+            msgs: vec![bank_msg()],
+            context: Addr::unchecked(MEMBER).into(),
+        },
+        AuthorizationRequest {
+            msgs: vec![staking_msg()],
+            context: Addr::unchecked(MEMBER).into(),
+        },
+        AuthorizationRequest {
+            msgs: vec![bank_msg()],
+            context: Addr::unchecked(STRANGER).into(),
+        },
+    ];
+
+    let batch: IsAuthorizedBatchResponse = app
+        .wrap()
+        .query_wasm_smart(
+            any_addr.clone(),
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorizedBatch {
+                requests: requests.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(batch.responses.len(), 3);
+    assert!(batch.responses[0].authorized);
+    // Authorized by the second child only
+    assert!(batch.responses[1].authorized);
+    assert!(!batch.responses[2].authorized);
+
+    for (request, response) in requests.into_iter().zip(batch.responses) {
+        let single: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                any_addr.clone(),
+                &AuthoriazationQueryMsg::<Empty>::IsAuthorized {
+                    msgs: request.msgs,
+                    context: request.context,
+                },
+            )
+            .unwrap();
+        assert_eq!(single, response);
+    }
+}
+
+#[test]
+fn test_per_message_verdicts() {
+    let mut app = App::default();
+    let (any_addr, bank_filter, staking_filter) = setup_tree(&mut app);
+
+    // No single child authorizes both messages together...
+    let msgs = vec![bank_msg(), staking_msg()];
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query_wasm_smart(
+            any_addr.clone(),
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorized {
+                msgs: msgs.clone(),
+                context: Addr::unchecked(MEMBER).into(),
+            },
+        )
+        .unwrap();
+    assert!(!response.authorized);
+
+    // ...but each message is authorized by one of them
+    let response: IsAuthorizedPerMessageResponse = app
+        .wrap()
+        .query_wasm_smart(
+            any_addr.clone(),
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorizedPerMessage {
+                msgs,
+                context: Addr::unchecked(MEMBER).into(),
+            },
+        )
+        .unwrap();
+    assert_eq!(response.responses.len(), 2);
+    assert!(response.responses[0].authorized);
+    assert!(response.responses[1].authorized);
+
+    // A rejected message reports the reason of every child
+    let response: IsAuthorizedPerMessageResponse = app
+        .wrap()
+        .query_wasm_smart(
+            any_addr,
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorizedPerMessage {
+                msgs: vec![bank_msg()],
+                context: Addr::unchecked(STRANGER).into(),
+            },
+        )
+        .unwrap();
+    assert_eq!(response.responses.len(), 1);
+    let reason = response.responses[0].reason.as_ref().unwrap();
+    assert!(reason.contains(bank_filter.as_str()));
+    assert!(reason.contains(staking_filter.as_str()));
+}
+
+#[test]
+fn test_update_sender_must_match_caller() {
+    let mut app = App::default();
+    let creator = Addr::unchecked(CREATOR);
+
+    let whitelist_id = app.store_code(whitelist());
+    let whitelist_addr = app
+        .instantiate_contract(
+            whitelist_id,
+            creator.clone(),
+            &whitelist::msg::InstantiateMsg {
+                owner: creator.clone(),
+            },
+            &[],
+            "Whitelist",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        creator.clone(),
+        whitelist_addr.clone(),
+        &AuthoriazationExecuteMsg::<whitelist::msg::ExecuteMsg>::Extension(
+            whitelist::msg::ExecuteMsg::Allow {
+                addr: MEMBER.to_string(),
+                max_uses: None,
+            },
+        ),
+        &[],
+    )
+    .unwrap();
+    let any_addr = instantiate_any(&mut app, vec![whitelist_addr], None);
+
+    let update = AuthoriazationExecuteMsg::<Empty>::UpdateExecutedAuthorizationState {
+        msgs: vec![bank_msg()],
+        context: Addr::unchecked(MEMBER).into(),
+    };
+
+    // A third party can't trigger an update on behalf of MEMBER
+    let err = app
+        .execute_contract(Addr::unchecked(STRANGER), any_addr.clone(), &update, &[])
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("cannot specify a sender other than the caller"));
+
+    // MEMBER can update on its own behalf, and the parent on anyone's
+    app.execute_contract(Addr::unchecked(MEMBER), any_addr.clone(), &update, &[])
+        .unwrap();
+    app.execute_contract(creator, any_addr.clone(), &update, &[])
+        .unwrap();
+
+    // Updates are only forwarded if some child authorizes the messages
+    let err = app
+        .execute_contract(
+            Addr::unchecked(STRANGER),
+            any_addr,
+            &AuthoriazationExecuteMsg::<Empty>::UpdateExecutedAuthorizationState {
+                msgs: vec![bank_msg()],
+                context: Addr::unchecked(STRANGER).into(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("No sub authorization passed"));
+}
+
+/// Instantiates any[short_verdicts]
+fn setup_short_verdicts(app: &mut App) -> Addr {
+    let short_id = app.store_code(short_verdicts());
+    let short_addr = app
+        .instantiate_contract(
+            short_id,
+            Addr::unchecked(CREATOR),
+            &Empty {},
+            &[],
+            "Short",
+            None,
+        )
+        .unwrap();
+    instantiate_any(app, vec![short_addr], None)
+}
+
+#[test]
+fn test_missing_batch_verdicts_are_rejected() {
+    let mut app = App::default();
+    let any_addr = setup_short_verdicts(&mut app);
+
+    let requests = vec![
+        AuthorizationRequest {
+            msgs: vec![],
+            context: Addr::unchecked(MEMBER).into(),
+        };
+        2
+    ];
+    let batch: IsAuthorizedBatchResponse = app
+        .wrap()
+        .query_wasm_smart(
+            any_addr,
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorizedBatch { requests },
+        )
+        .unwrap();
+    assert_eq!(batch.responses.len(), 2);
+    for response in batch.responses {
+        assert!(!response.authorized);
+        assert!(response
+            .reason
+            .unwrap()
+            .contains("Expected 2 verdicts but got 1"));
+    }
+}
+
+#[test]
+fn test_missing_per_message_verdicts_are_rejected() {
+    let mut app = App::default();
+    let any_addr = setup_short_verdicts(&mut app);
+
+    let response: IsAuthorizedPerMessageResponse = app
+        .wrap()
+        .query_wasm_smart(
+            any_addr,
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorizedPerMessage {
+                msgs: vec![bank_msg(), staking_msg()],
+                context: Addr::unchecked(MEMBER).into(),
+            },
+        )
+        .unwrap();
+    assert_eq!(response.responses.len(), 2);
+    assert!(response.responses.iter().all(|r| !r.authorized));
+}
@@ -1,44 +1,140 @@
-#[cfg(not(feature = "library"))]
-use cosmwasm_std::entry_point;
+use std::marker::PhantomData;
+
 use cosmwasm_std::{
-    Addr, Binary, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    Addr, CosmosMsg, CustomMsg, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
 };
-use cw2::set_contract_version;
-use cw_authorizations::msg::{AuthoriazationExecuteMsg, AuthoriazationQueryMsg};
+use cw_authorizations::migration::Version;
+use cw_authorizations::msg::AuthorizationContext;
 use cw_authorizations::{Authorization, AuthorizationError};
+use cw_authorizations_derive::authorization_contract;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg};
-use crate::state::WhitelistState;
-
-const CONTRACT_NAME: &str = "crates.io:whitelist";
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+use crate::migrations::migrate_from_v0_1;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg};
+use crate::state::{Entry, WhitelistState};
 
-pub struct WhitelistContract {
+/// `C` is the custom message type of the chain. The whitelist doesn't look at
+/// the messages, but being generic allows it to be used on any chain.
+pub struct WhitelistContract<C = Empty> {
     state: WhitelistState,
+    custom: PhantomData<C>,
 }
 
-impl Authorization<ExecuteMsg> for WhitelistContract {
+impl<C> WhitelistContract<C> {
+    pub fn instantiate(
+        &self,
+        deps: DepsMut,
+        msg: InstantiateMsg,
+    ) -> Result<(), AuthorizationError<ContractError>> {
+        self.state.ownership.initialize(deps.storage, msg.owner)?;
+        Ok(())
+    }
+
+    pub fn migrate(
+        &self,
+        deps: DepsMut,
+        _msg: MigrateMsg,
+        from_version: &Version,
+    ) -> Result<(), AuthorizationError<ContractError>> {
+        if *from_version < Version::new(0, 2, 0) {
+            migrate_from_v0_1(deps.storage, &self.state)?;
+        }
+        Ok(())
+    }
+}
+
+#[authorization_contract(
+    instantiate = InstantiateMsg,
+    execute = ExecuteMsg,
+    error = ContractError,
+    migrate = MigrateMsg
+)]
+impl<C: CustomMsg> Authorization<ExecuteMsg, Empty, ContractError, C> for WhitelistContract<C> {
     fn new() -> Self {
         WhitelistContract {
             state: WhitelistState::new(),
+            custom: PhantomData,
         }
     }
 
+    fn contract_type(&self) -> String {
+        "whitelist".to_string()
+    }
+
     fn is_authorized(
         &self,
         deps: Deps,
-        _msgs: &Vec<CosmosMsg>,
-        sender: &Addr,
-    ) -> Result<bool, AuthorizationError> {
-        Ok(self
+        _env: &Env,
+        _msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
+    ) -> Result<bool, AuthorizationError<ContractError>> {
+        if self
             .state
             .authorized
-            .may_load(deps.storage, sender.to_string())?
-            .is_some())
+            .may_load(deps.storage, context.sender.to_string())?
+            .is_none()
+        {
+            return Err(AuthorizationError::Unauthorized {
+                reason: Some(format!("{} is not whitelisted", context.sender)),
+            });
+        }
+        Ok(true)
+    }
+
+    fn is_stateful(&self) -> bool {
+        true
     }
 
-    fn get_sub_authorizations(&self, _deps: Deps) -> Result<Vec<Addr>, AuthorizationError> {
+    /// Uses up one use of the sender's entry, removing it when none are left.
+    /// Entries without a use count are left untouched.
+    fn update_own_state(
+        &self,
+        deps: DepsMut,
+        _env: &Env,
+        _msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
+        original_sender: &Addr,
+    ) -> Result<Response, AuthorizationError<ContractError>> {
+        let key = context.sender.to_string();
+        let uses = match self.state.authorized.may_load(deps.storage, key.clone())? {
+            Some(Entry {
+                max_uses: Some(uses),
+            }) => uses,
+            _ => return Ok(Response::default()),
+        };
+        let updaters = self
+            .state
+            .updaters
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        if original_sender != &context.sender && !updaters.contains(original_sender) {
+            return Err(AuthorizationError::Unauthorized {
+                reason: Some(
+                    "Only the sender or an updater can record executions for a sender".to_string(),
+                ),
+            });
+        }
+
+        if uses > 1 {
+            self.state.authorized.save(
+                deps.storage,
+                key,
+                &Entry {
+                    max_uses: Some(uses - 1),
+                },
+            )?;
+            return Ok(Response::default().add_attribute("action", "record_execution"));
+        }
+        self.state.authorized.remove(deps.storage, key);
+        Ok(Response::default()
+            .add_attribute("action", "record_execution")
+            .add_attribute("removed", context.sender.to_string()))
+    }
+
+    fn get_sub_authorizations(
+        &self,
+        _deps: Deps,
+    ) -> Result<Vec<Addr>, AuthorizationError<ContractError>> {
         Ok(vec![])
     }
 
@@ -48,56 +144,32 @@ impl Authorization<ExecuteMsg> for WhitelistContract {
         _env: Env,
         info: MessageInfo,
         msg: ExecuteMsg,
-    ) -> Result<Response, AuthorizationError> {
+    ) -> Result<Response, AuthorizationError<ContractError>> {
+        self.state
+            .ownership
+            .assert_owner(deps.storage, &info.sender)?;
         match msg {
-            ExecuteMsg::Allow { addr } => {
-                if info.sender != self.state.owner.load(deps.storage)? {
-                    return Err(AuthorizationError::Unauthorized {
-                        //reason: Some("Only the dao can add authorizations".to_string()),
-                    }
-                    .into());
+            ExecuteMsg::Allow { addr, max_uses } => {
+                if max_uses == Some(0) {
+                    return Err(AuthorizationError::ContractError(ContractError::NoUses {}));
                 }
-                self.state.authorized.save(deps.storage, addr, &Empty {})?;
+                self.state
+                    .authorized
+                    .save(deps.storage, addr, &Entry { max_uses })?;
                 Ok(Response::default().add_attribute("action", "allow"))
             }
             ExecuteMsg::Remove { addr } => {
-                if info.sender != self.state.owner.load(deps.storage)? {
-                    return Err(AuthorizationError::Unauthorized {
-                     //   reason: Some("Only the dao can remove authorizations".to_string()),
-                    }
-                    .into());
-                }
                 self.state.authorized.remove(deps.storage, addr);
                 Ok(Response::default().add_attribute("action", "remove"))
             }
+            ExecuteMsg::SetUpdaters { updaters } => {
+                let updaters = updaters
+                    .iter()
+                    .map(|addr| deps.api.addr_validate(addr))
+                    .collect::<StdResult<Vec<_>>>()?;
+                self.state.updaters.save(deps.storage, &updaters)?;
+                Ok(Response::default().add_attribute("action", "set_updaters"))
+            }
         }
     }
 }
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    msg: InstantiateMsg,
-) -> Result<Response, ContractError> {
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    let contract = WhitelistContract::new();
-    contract.state.owner.save(deps.storage, &msg.dao)?;
-    Ok(Response::default().add_attribute("action", "instantiate"))
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: AuthoriazationExecuteMsg<ExecuteMsg>,
-) -> Result<Response, AuthorizationError> {
-    WhitelistContract::new().execute(deps, env, info, msg)
-}
-
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: AuthoriazationQueryMsg) -> StdResult<Binary> {
-    WhitelistContract::new().query(deps, env, msg)
-}
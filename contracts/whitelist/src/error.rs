@@ -1,11 +1,17 @@
-use cosmwasm_std::StdError;
+use cw_authorizations::ErrorCode;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ContractError {
-    #[error("{0}")]
-    Std(#[from] StdError),
+    #[error("An entry needs at least one use")]
+    NoUses {},
+}
 
-    #[error("Custom Error val: {val:?}")]
-    CustomError { val: String },
+impl ErrorCode for ContractError {
+    fn code(&self) -> String {
+        match self {
+            ContractError::NoUses {} => "no_uses",
+        }
+        .to_string()
+    }
 }
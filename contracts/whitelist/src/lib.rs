@@ -1,6 +1,10 @@
 pub mod contract;
 mod error;
+mod migrations;
 pub mod msg;
 pub mod state;
 
 pub use crate::error::ContractError;
+
+#[cfg(test)]
+mod tests;
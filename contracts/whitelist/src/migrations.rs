@@ -0,0 +1,19 @@
+use cosmwasm_std::{Addr, Storage};
+use cw_authorizations::AuthorizationError;
+use cw_storage_plus::Item;
+
+use crate::state::WhitelistState;
+use crate::ContractError;
+
+/// Before 0.2.0 the owner was stored under the "dao" key
+const OWNER_V0_1: Item<Addr> = Item::new("dao");
+
+pub fn migrate_from_v0_1(
+    storage: &mut dyn Storage,
+    state: &WhitelistState,
+) -> Result<(), AuthorizationError<ContractError>> {
+    let owner = OWNER_V0_1.load(storage)?;
+    OWNER_V0_1.remove(storage);
+    state.ownership.initialize(storage, owner)?;
+    Ok(())
+}
@@ -5,18 +5,29 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
-    pub dao: Addr,
+    pub owner: Addr,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    Allow { addr: String },
-    Remove { addr: String },
+    /// Whitelists addr. With max_uses, the entry is removed after that many
+    /// executions are recorded.
+    Allow {
+        addr: String,
+        max_uses: Option<u64>,
+    },
+    Remove {
+        addr: String,
+    },
+    /// Sets the contracts allowed to record executions on behalf of any sender
+    SetUpdaters {
+        updaters: Vec<String>,
+    },
 }
 
 impl CustomMsg for ExecuteMsg {}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {}
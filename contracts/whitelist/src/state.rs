@@ -1,16 +1,39 @@
 use cosmwasm_std::Addr;
+use cw_authorizations::ownership::OwnershipState;
 use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A whitelisted address. Entries stored before use counts were added were
+/// empty objects, which read as entries without a limit.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Entry {
+    /// How many more executions the address can be authorized for. The entry
+    /// is removed when none are left.
+    #[serde(default)]
+    pub max_uses: Option<u64>,
+}
 
 pub struct WhitelistState {
-    pub owner: Item<'static, Addr>,
-    pub authorized: Map<'static, String, cosmwasm_std::Empty>,
+    pub ownership: OwnershipState,
+    pub authorized: Map<'static, String, Entry>,
+    /// Contracts allowed to record executions on behalf of any sender, like
+    /// the executor or a parent composite
+    pub updaters: Item<'static, Vec<Addr>>,
 }
 
 impl WhitelistState {
     pub fn new() -> Self {
         WhitelistState {
-            owner: Item::new("dao"),
+            ownership: OwnershipState::new(),
             authorized: Map::new("authorized"),
+            updaters: Item::new("updaters"),
         }
     }
 }
+
+impl Default for WhitelistState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
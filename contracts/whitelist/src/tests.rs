@@ -0,0 +1,218 @@
+use cosmwasm_std::{Addr, DepsMut, Empty, Env, MessageInfo, Response, StdError};
+use cw_authorizations::msg::{
+    AuthoriazationExecuteMsg, AuthoriazationQueryMsg, IsAuthorizedResponse,
+};
+use cw_authorizations::ownership::Ownership;
+use cw_authorizations::{AuthorizationError, ErrorCode};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::Item;
+
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg};
+use crate::ContractError;
+
+const CREATOR: &str = "creator";
+
+fn contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+/// Stores the owner as it was stored before 0.2.0
+fn instantiate_v0_1(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, StdError> {
+    cw2::set_contract_version(deps.storage, "crates.io:whitelist", "0.1.0")?;
+    Item::new("dao").save(deps.storage, &msg.owner)?;
+    Ok(Response::default())
+}
+
+fn contract_v0_1() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        instantiate_v0_1,
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+#[test]
+fn test_migrate_from_v0_1() {
+    let mut app = App::default();
+    let legacy_id = app.store_code(contract_v0_1());
+    let addr = app
+        .instantiate_contract(
+            legacy_id,
+            Addr::unchecked(CREATOR),
+            &InstantiateMsg {
+                owner: Addr::unchecked(CREATOR),
+            },
+            &[],
+            "Whitelist",
+            Some(CREATOR.to_string()),
+        )
+        .unwrap();
+
+    let code_id = app.store_code(contract());
+    app.migrate_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &MigrateMsg {},
+        code_id,
+    )
+    .unwrap();
+
+    // The dao became the owner and can still manage the whitelist
+    let ownership: Ownership = app
+        .wrap()
+        .query_wasm_smart(addr.clone(), &AuthoriazationQueryMsg::<Empty>::Ownership {})
+        .unwrap();
+    assert_eq!(ownership.owner, Some(Addr::unchecked(CREATOR)));
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr,
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::Allow {
+            addr: "someone".to_string(),
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+}
+
+fn instantiate_whitelist(app: &mut App) -> Addr {
+    let code_id = app.store_code(contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(CREATOR),
+        &InstantiateMsg {
+            owner: Addr::unchecked(CREATOR),
+        },
+        &[],
+        "Whitelist",
+        None,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_denial_reasons() {
+    let mut app = App::default();
+    let addr = instantiate_whitelist(&mut app);
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::Allow {
+            addr: "someone".to_string(),
+            max_uses: None,
+        }),
+        &[],
+    )
+    .unwrap();
+
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &addr,
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorized {
+                context: Addr::unchecked("someone else").into(),
+                msgs: vec![],
+            },
+        )
+        .unwrap();
+    assert!(!response.authorized);
+    assert_eq!(response.code.unwrap(), "unauthorized");
+    assert_eq!(response.reason.unwrap(), "someone else is not whitelisted");
+
+    // Authorized responses don't carry a reason
+    let response: IsAuthorizedResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &addr,
+            &AuthoriazationQueryMsg::<Empty>::IsAuthorized {
+                context: Addr::unchecked("someone").into(),
+                msgs: vec![],
+            },
+        )
+        .unwrap();
+    assert_eq!(response, IsAuthorizedResponse::authorized());
+}
+
+#[test]
+fn test_max_uses() {
+    let mut app = App::default();
+    let addr = instantiate_whitelist(&mut app);
+    let allow = |app: &mut App, max_uses: Option<u64>| {
+        app.execute_contract(
+            Addr::unchecked(CREATOR),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::Allow {
+                addr: "someone".to_string(),
+                max_uses,
+            }),
+            &[],
+        )
+    };
+    let is_authorized = |app: &App| -> bool {
+        let response: IsAuthorizedResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &addr,
+                &AuthoriazationQueryMsg::<Empty>::IsAuthorized {
+                    context: Addr::unchecked("someone").into(),
+                    msgs: vec![],
+                },
+            )
+            .unwrap();
+        response.authorized
+    };
+    let record = |app: &mut App, caller: &str| {
+        app.execute_contract(
+            Addr::unchecked(caller),
+            addr.clone(),
+            &AuthoriazationExecuteMsg::<ExecuteMsg>::UpdateExecutedAuthorizationState {
+                msgs: vec![],
+                context: Addr::unchecked("someone").into(),
+            },
+            &[],
+        )
+    };
+
+    let err: AuthorizationError<ContractError> =
+        allow(&mut app, Some(0)).unwrap_err().downcast().unwrap();
+    assert!(matches!(
+        err,
+        AuthorizationError::ContractError(ContractError::NoUses {})
+    ));
+    assert_eq!(err.code(), "no_uses");
+    allow(&mut app, Some(2)).unwrap();
+
+    // Only the sender or an updater can use up the entry
+    record(&mut app, "someone else").unwrap_err();
+    record(&mut app, "someone").unwrap();
+    assert!(is_authorized(&app));
+    app.execute_contract(
+        Addr::unchecked(CREATOR),
+        addr.clone(),
+        &AuthoriazationExecuteMsg::<ExecuteMsg>::Extension(ExecuteMsg::SetUpdaters {
+            updaters: vec!["executor".to_string()],
+        }),
+        &[],
+    )
+    .unwrap();
+    record(&mut app, "executor").unwrap();
+    assert!(!is_authorized(&app));
+
+    // Entries without a use count are never used up
+    allow(&mut app, None).unwrap();
+    record(&mut app, "someone").unwrap();
+    record(&mut app, "someone").unwrap();
+    assert!(is_authorized(&app));
+}
@@ -0,0 +1,225 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, parse_quote, Ident, ItemImpl, PathArguments, Token, Type};
+
+struct Arg {
+    key: Ident,
+    value: Type,
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse()?;
+        Ok(Arg { key, value })
+    }
+}
+
+struct Args {
+    instantiate: Type,
+    execute: Type,
+    query: Type,
+    error: Type,
+    migrate: Option<Type>,
+    custom: Option<Type>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut instantiate = None;
+        let mut execute = parse_quote!(::cosmwasm_std::Empty);
+        let mut query = parse_quote!(::cosmwasm_std::Empty);
+        let mut error = parse_quote!(::cw_authorizations::error::EmptyError);
+        let mut migrate = None;
+        let mut custom = None;
+
+        for arg in Punctuated::<Arg, Token![,]>::parse_terminated(input)? {
+            match arg.key.to_string().as_str() {
+                "instantiate" => instantiate = Some(arg.value),
+                "execute" => execute = arg.value,
+                "query" => query = arg.value,
+                "error" => error = arg.value,
+                "migrate" => migrate = Some(arg.value),
+                "custom" => custom = Some(arg.value),
+                _ => {
+                    return Err(syn::Error::new(
+                        arg.key.span(),
+                        "expected one of: instantiate, execute, query, error, migrate, custom",
+                    ))
+                }
+            }
+        }
+
+        let instantiate = instantiate.ok_or_else(|| {
+            syn::Error::new(input.span(), "the instantiate message type is required")
+        })?;
+        Ok(Args {
+            instantiate,
+            execute,
+            query,
+            error,
+            migrate,
+            custom,
+        })
+    }
+}
+
+/// Generates the `instantiate`, `execute`, `query` and `reply` entry points for
+/// an `Authorization` implementation:
+///
+/// ```ignore
+/// #[authorization_contract(instantiate = InstantiateMsg, execute = ExecuteMsg, query = QueryMsg, error = MyError)]
+/// impl Authorization<ExecuteMsg, QueryMsg, MyError> for MyContract { ... }
+/// ```
+///
+/// Only `instantiate` is required. The other types default to the defaults of
+/// the `Authorization` trait. The contract must provide an inherent
+/// `fn instantiate(&self, deps: DepsMut, msg: InstantiateMsg) -> Result<(), AuthorizationError<MyError>>`.
+///
+/// If `migrate = MigrateMsg` is specified, a migrate entry point is generated
+/// as well. It refuses to migrate from a different contract or from a newer
+/// version, and calls the inherent
+/// `fn migrate(&self, deps: DepsMut, msg: MigrateMsg, from_version: &Version) -> Result<(), AuthorizationError<MyError>>`
+/// before storing the new cw2 version.
+///
+/// The instantiate entry point stores the cw2 version using the crate name
+/// (prefixed with "crates.io:") and version, and the reply entry point
+/// forwards to `Authorization::reply`. As with the
+/// hand-written entry points, they are only exported when the "library"
+/// feature is disabled.
+///
+/// If the contract is generic over the custom message type, the entry points
+/// use `Empty` unless `custom = MyChainMsg` is specified, in which case they
+/// authorize `CosmosMsg<MyChainMsg>`. The contract type is then instantiated
+/// as `MyContract<MyChainMsg>`, so it must have no other type parameters.
+#[proc_macro_attribute]
+pub fn authorization_contract(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as Args);
+    let item = parse_macro_input!(item as ItemImpl);
+
+    // Strip the generics so that the defaults (or the custom message type) are
+    // used for the entry points.
+    let mut contract = match item.self_ty.as_ref() {
+        Type::Path(path) => path.clone(),
+        ty => {
+            return syn::Error::new_spanned(ty, "expected a contract type")
+                .to_compile_error()
+                .into()
+        }
+    };
+    let Args {
+        instantiate,
+        execute,
+        query,
+        error,
+        migrate,
+        custom,
+    } = args;
+    if let Some(segment) = contract.path.segments.last_mut() {
+        segment.arguments = match &custom {
+            Some(custom) => PathArguments::AngleBracketed(parse_quote!(<#custom>)),
+            None => PathArguments::None,
+        };
+    }
+    let custom = custom.unwrap_or_else(|| parse_quote!(::cosmwasm_std::Empty));
+
+    let authorization =
+        quote!(::cw_authorizations::Authorization<#execute, #query, #error, #custom>);
+    let contract = quote!(<#contract as #authorization>);
+
+    // The migrate entry point is only generated if the contract supports
+    // migrations.
+    let migrate = migrate.map(|migrate| {
+        quote! {
+            #[cfg_attr(not(feature = "library"), ::cosmwasm_std::entry_point)]
+            pub fn migrate(
+                mut deps: ::cosmwasm_std::DepsMut,
+                _env: ::cosmwasm_std::Env,
+                msg: #migrate,
+            ) -> ::std::result::Result<
+                ::cosmwasm_std::Response,
+                ::cw_authorizations::AuthorizationError<#error>,
+            > {
+                let name = concat!("crates.io:", env!("CARGO_PKG_NAME"));
+                let version = env!("CARGO_PKG_VERSION");
+                let from_version =
+                    ::cw_authorizations::migration::assert_can_migrate(deps.storage, name, version)?;
+                let contract = #contract::new();
+                contract.migrate(deps.branch(), msg, &from_version)?;
+                ::cw2::set_contract_version(deps.storage, name, version)?;
+                Ok(::cosmwasm_std::Response::default()
+                    .add_attribute("action", "migrate")
+                    .add_attribute("from_version", from_version.to_string())
+                    .add_attribute("to_version", version))
+            }
+        }
+    });
+
+    let expanded = quote! {
+        #item
+
+        #[cfg_attr(not(feature = "library"), ::cosmwasm_std::entry_point)]
+        pub fn instantiate(
+            deps: ::cosmwasm_std::DepsMut,
+            _env: ::cosmwasm_std::Env,
+            _info: ::cosmwasm_std::MessageInfo,
+            msg: #instantiate,
+        ) -> ::std::result::Result<
+            ::cosmwasm_std::Response,
+            ::cw_authorizations::AuthorizationError<#error>,
+        > {
+            ::cw2::set_contract_version(
+                deps.storage,
+                concat!("crates.io:", env!("CARGO_PKG_NAME")),
+                env!("CARGO_PKG_VERSION"),
+            )?;
+            let contract = #contract::new();
+            contract.instantiate(deps, msg)?;
+            Ok(::cosmwasm_std::Response::default().add_attribute("action", "instantiate"))
+        }
+
+        #[cfg_attr(not(feature = "library"), ::cosmwasm_std::entry_point)]
+        pub fn execute(
+            deps: ::cosmwasm_std::DepsMut,
+            env: ::cosmwasm_std::Env,
+            info: ::cosmwasm_std::MessageInfo,
+            msg: ::cw_authorizations::msg::AuthoriazationExecuteMsg<#execute, #custom>,
+        ) -> ::std::result::Result<
+            ::cosmwasm_std::Response,
+            ::cw_authorizations::AuthorizationError<#error>,
+        > {
+            let contract = #contract::new();
+            #contract::execute(&contract, deps, env, info, msg)
+        }
+
+        #[cfg_attr(not(feature = "library"), ::cosmwasm_std::entry_point)]
+        pub fn query(
+            deps: ::cosmwasm_std::Deps,
+            env: ::cosmwasm_std::Env,
+            msg: ::cw_authorizations::msg::AuthoriazationQueryMsg<#query, #custom>,
+        ) -> ::cosmwasm_std::StdResult<::cosmwasm_std::Binary> {
+            let contract = #contract::new();
+            #contract::query(&contract, deps, env, msg)
+        }
+
+        #[cfg_attr(not(feature = "library"), ::cosmwasm_std::entry_point)]
+        pub fn reply(
+            deps: ::cosmwasm_std::DepsMut,
+            env: ::cosmwasm_std::Env,
+            msg: ::cosmwasm_std::Reply,
+        ) -> ::std::result::Result<
+            ::cosmwasm_std::Response,
+            ::cw_authorizations::AuthorizationError<#error>,
+        > {
+            let contract = #contract::new();
+            #contract::reply(&contract, deps, env, msg)
+        }
+
+        #migrate
+    };
+
+    expanded.into()
+}
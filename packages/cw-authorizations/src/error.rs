@@ -5,6 +5,14 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+/// The code reported when an authorization rejects a request without
+/// providing a more specific reason.
+pub const UNAUTHORIZED_CODE: &str = "unauthorized";
+
+/// The code reported when a sub-authorization could not be queried. Composite
+/// authorizations treat this as a rejection.
+pub const QUERY_FAILED_CODE: &str = "query_failed";
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
 pub struct EmptyError {}
 
@@ -15,14 +23,70 @@ impl Display for EmptyError {
     }
 }
 
+/// A stable, machine-readable identifier for an error. This is what gets
+/// reported as the `code` of an `IsAuthorizedResponse` so that callers don't
+/// need to parse error messages to know why they were rejected.
+pub trait ErrorCode: Display {
+    fn code(&self) -> String;
+}
+
+impl ErrorCode for EmptyError {
+    fn code(&self) -> String {
+        "contract_error".to_string()
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum AuthorizationError<ErrorExt = EmptyError> {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("Unauthorized")]
-    Unauthorized {},
+    #[error("Unauthorized {reason:?}")]
+    Unauthorized { reason: Option<String> },
+
+    #[error("{addr} is not a compatible authorization: {reason}")]
+    IncompatibleChild { addr: String, reason: String },
+
+    #[error("Adding {addr} would create a cycle in the authorization tree")]
+    CycleDetected { addr: String },
+
+    #[error("The authorization subtree would be deeper than the maximum of {max_depth}")]
+    MaxDepthExceeded { max_depth: u32 },
+
+    #[error("Invalid migration: {reason}")]
+    InvalidMigration { reason: String },
 
     #[error("ContractError: {0}")]
     ContractError(ErrorExt),
 }
+
+impl<ErrorExt: ErrorCode> AuthorizationError<ErrorExt> {
+    /// A human-readable explanation of the error.
+    pub fn reason(&self) -> String {
+        match self {
+            AuthorizationError::Std(e) => e.to_string(),
+            AuthorizationError::Unauthorized { reason } => {
+                reason.clone().unwrap_or_else(|| "Unauthorized".to_string())
+            }
+            AuthorizationError::IncompatibleChild { .. }
+            | AuthorizationError::CycleDetected { .. }
+            | AuthorizationError::MaxDepthExceeded { .. }
+            | AuthorizationError::InvalidMigration { .. } => self.to_string(),
+            AuthorizationError::ContractError(e) => e.to_string(),
+        }
+    }
+}
+
+impl<ErrorExt: ErrorCode> ErrorCode for AuthorizationError<ErrorExt> {
+    fn code(&self) -> String {
+        match self {
+            AuthorizationError::Std(_) => "std_error".to_string(),
+            AuthorizationError::Unauthorized { .. } => UNAUTHORIZED_CODE.to_string(),
+            AuthorizationError::IncompatibleChild { .. } => "incompatible_child".to_string(),
+            AuthorizationError::CycleDetected { .. } => "cycle_detected".to_string(),
+            AuthorizationError::MaxDepthExceeded { .. } => "max_depth_exceeded".to_string(),
+            AuthorizationError::InvalidMigration { .. } => "invalid_migration".to_string(),
+            AuthorizationError::ContractError(e) => e.code(),
+        }
+    }
+}
@@ -1,52 +1,243 @@
 use cosmwasm_std::{
-    to_binary, wasm_execute, Addr, Binary, CosmosMsg, CustomMsg, Deps, DepsMut, Empty, Env,
-    MessageInfo, Reply, ReplyOn, Response, StdResult, SubMsg,
+    to_binary, Addr, Binary, CosmosMsg, CustomMsg, Deps, DepsMut, Empty, Env, MessageInfo, Reply,
+    ReplyOn, Response, StdError, StdResult, SubMsg, WasmMsg,
 };
 
-use crate::error::{AuthorizationError, EmptyError};
+use crate::error::{AuthorizationError, EmptyError, ErrorCode, QUERY_FAILED_CODE};
 use crate::msg;
-use crate::msg::IsAuthorizedResponse;
+use crate::msg::{
+    AuthorizationContext, AuthorizationRequest, DecisionNode, FailedUpdatesResponse, FailurePolicy,
+    InfoResponse, IsAuthorizedBatchResponse, IsAuthorizedPerMessageResponse, IsAuthorizedResponse,
+};
+use crate::ownership::{OwnershipAction, OwnershipState};
+use crate::updates::UpdateFailures;
+
+pub(crate) const UPDATE_REPLY_ID: u64 = 1000;
 
-const UPDATE_REPLY_ID: u64 = 1000;
+/// The version of the authorization interface implemented by this crate.
+pub const INTERFACE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub trait Authorization<ExecuteExt = Empty, QueryExt = Empty, ErrorExt = EmptyError>
+/// The authorization interface. `C` is the chain-specific custom message type
+/// of the `CosmosMsg<C>`s being authorized. Chains without custom messages can
+/// use the default (`Empty`).
+pub trait Authorization<ExecuteExt = Empty, QueryExt = Empty, ErrorExt = EmptyError, C = Empty>
 where
     ExecuteExt: CustomMsg,
     QueryExt: CustomMsg,
+    ErrorExt: ErrorCode,
+    C: CustomMsg,
 {
     // Required
     fn new() -> Self;
+    /// A short identifier for the type of authorization (i.e.: whitelist).
+    /// This is reported when explaining decisions.
+    fn contract_type(&self) -> String;
     fn get_sub_authorizations(&self, deps: Deps)
         -> Result<Vec<Addr>, AuthorizationError<ErrorExt>>;
+    /// Returns whether the context's sender is allowed to execute msgs.
+    /// Implementors should prefer returning an error describing why the
+    /// request was rejected over Ok(false), as the error's code and reason are
+    /// forwarded to the caller in the IsAuthorizedResponse.
+    ///
+    /// The env is provided so that implementations can make time-based
+    /// decisions (i.e.: expirations or cooldowns), and the context so that
+    /// they can inspect the funds attached to the execution.
+    #[allow(clippy::ptr_arg)]
     fn is_authorized(
         &self,
         deps: Deps,
-        msgs: &Vec<CosmosMsg>,
-        sender: &Addr,
+        env: &Env,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
     ) -> Result<bool, AuthorizationError<ErrorExt>>;
 
     fn query_authorizations(
         &self,
         deps: Deps,
-        msgs: Vec<CosmosMsg>,
-        sender: Addr,
+        env: Env,
+        msgs: Vec<CosmosMsg<C>>,
+        context: AuthorizationContext,
+    ) -> StdResult<Binary> {
+        to_binary(&IsAuthorizedResponse::from_result(
+            self.is_authorized(deps, &env, &msgs, &context),
+        ))
+    }
+
+    /// Checks several requests at once. By default each request is checked
+    /// independently. Composite authorizations should override this to
+    /// forward the whole batch to each of their children in a single query.
+    fn is_authorized_batch(
+        &self,
+        deps: Deps,
+        env: &Env,
+        requests: &[AuthorizationRequest<C>],
+    ) -> Result<Vec<IsAuthorizedResponse>, AuthorizationError<ErrorExt>> {
+        Ok(requests
+            .iter()
+            .map(|request| {
+                IsAuthorizedResponse::from_result(self.is_authorized(
+                    deps,
+                    env,
+                    &request.msgs,
+                    &request.context,
+                ))
+            })
+            .collect())
+    }
+
+    fn query_authorizations_batch(
+        &self,
+        deps: Deps,
+        env: Env,
+        requests: Vec<AuthorizationRequest<C>>,
+    ) -> StdResult<Binary> {
+        let responses = self
+            .is_authorized_batch(deps, &env, &requests)
+            .map_err(|e| StdError::generic_err(e.reason()))?;
+        to_binary(&IsAuthorizedBatchResponse { responses })
+    }
+
+    /// Returns one verdict per message. By default each message is checked on
+    /// its own with is_authorized. Note that a set of messages that are all
+    /// authorized individually is not necessarily authorized as a whole.
+    fn is_authorized_per_message(
+        &self,
+        deps: Deps,
+        env: &Env,
+        msgs: &[CosmosMsg<C>],
+        context: &AuthorizationContext,
+    ) -> Result<Vec<IsAuthorizedResponse>, AuthorizationError<ErrorExt>> {
+        Ok(msgs
+            .iter()
+            .map(|msg| {
+                IsAuthorizedResponse::from_result(self.is_authorized(
+                    deps,
+                    env,
+                    &vec![msg.clone()],
+                    context,
+                ))
+            })
+            .collect())
+    }
+
+    fn query_authorizations_per_message(
+        &self,
+        deps: Deps,
+        env: Env,
+        msgs: Vec<CosmosMsg<C>>,
+        context: AuthorizationContext,
+    ) -> StdResult<Binary> {
+        let responses = self
+            .is_authorized_per_message(deps, &env, &msgs, &context)
+            .map_err(|e| StdError::generic_err(e.reason()))?;
+        to_binary(&IsAuthorizedPerMessageResponse { responses })
+    }
+
+    /// Builds the decision tree for the messages. Sub-authorizations are
+    /// queried recursively, so composite authorizations get a full
+    /// explanation without having to override this.
+    fn explain(
+        &self,
+        deps: Deps,
+        env: &Env,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
+    ) -> Result<DecisionNode, AuthorizationError<ErrorExt>> {
+        let children = self
+            .get_sub_authorizations(deps)?
+            .into_iter()
+            .map(|child| {
+                deps.querier
+                    .query_wasm_smart(
+                        child.clone(),
+                        &msg::AuthoriazationQueryMsg::Explain::<Empty, C> {
+                            msgs: msgs.to_vec(),
+                            context: context.clone(),
+                        },
+                    )
+                    .unwrap_or_else(|e: StdError| DecisionNode {
+                        contract: child,
+                        contract_type: "unknown".to_string(),
+                        verdict: IsAuthorizedResponse::unauthorized(
+                            QUERY_FAILED_CODE,
+                            Some(e.to_string()),
+                        ),
+                        children: vec![],
+                    })
+            })
+            .collect();
+
+        Ok(DecisionNode {
+            contract: env.contract.address.clone(),
+            contract_type: self.contract_type(),
+            verdict: IsAuthorizedResponse::from_result(
+                self.is_authorized(deps, env, msgs, context),
+            ),
+            children,
+        })
+    }
+
+    fn query_explain(
+        &self,
+        deps: Deps,
+        env: Env,
+        msgs: Vec<CosmosMsg<C>>,
+        context: AuthorizationContext,
     ) -> StdResult<Binary> {
-        to_binary(&IsAuthorizedResponse {
-            authorized: self.is_authorized(deps, &msgs, &sender).unwrap_or(false),
+        let decision = self
+            .explain(deps, &env, &msgs, &context)
+            .map_err(|e| StdError::generic_err(e.reason()))?;
+        to_binary(&decision)
+    }
+
+    fn info(&self, deps: Deps) -> Result<InfoResponse, AuthorizationError<ErrorExt>> {
+        let version = cw2::get_contract_version(deps.storage)?;
+        Ok(InfoResponse {
+            contract_type: self.contract_type(),
+            contract_name: version.contract,
+            contract_version: version.version,
+            interface_version: INTERFACE_VERSION.to_string(),
+            children: self.get_sub_authorizations(deps)?,
+            stateful: self.is_stateful(),
         })
     }
 
+    fn query_info(&self, deps: Deps, _env: Env) -> StdResult<Binary> {
+        let info = self
+            .info(deps)
+            .map_err(|e| StdError::generic_err(e.reason()))?;
+        to_binary(&info)
+    }
+
     // Useful
+    /// Whether this authorization updates its own state when messages are
+    /// executed. Implementors that override update_own_state should return
+    /// true.
+    fn is_stateful(&self) -> bool {
+        false
+    }
+
     fn update_own_state(
         &self,
-        _deps: Deps,
-        _msgs: &Vec<CosmosMsg>,
-        _sender: &Addr,
+        _deps: DepsMut,
+        _env: &Env,
+        _msgs: &Vec<CosmosMsg<C>>,
+        _context: &AuthorizationContext,
         _original_sender: &Addr,
     ) -> Result<Response, AuthorizationError<ErrorExt>> {
         Ok(Response::default())
     }
 
+    /// What to do if updating the state of a child fails. Composites can
+    /// override this to let their owner choose per child.
+    fn failure_policy(
+        &self,
+        _deps: Deps,
+        _child: &Addr,
+    ) -> Result<FailurePolicy, AuthorizationError<ErrorExt>> {
+        Ok(FailurePolicy::default())
+    }
+
     // Helpers
     fn get_update_reply_id(&self) -> u64 {
         UPDATE_REPLY_ID
@@ -56,54 +247,81 @@ where
         ReplyOn::Error
     }
 
+    /// Generates the message that updates the state of a child according to
+    /// its failure policy.
+    fn child_update_msg(
+        &self,
+        deps: DepsMut,
+        child: &Addr,
+        msgs: &[CosmosMsg<C>],
+        context: &AuthorizationContext,
+    ) -> Result<SubMsg, AuthorizationError<ErrorExt>> {
+        let msg = to_binary(
+            &msg::AuthoriazationExecuteMsg::<ExecuteExt, C>::UpdateExecutedAuthorizationState {
+                msgs: msgs.to_vec(),
+                context: context.clone(),
+            },
+        )?;
+        self.child_update_submsg(deps, child, msg)
+    }
+
+    /// Wraps an update message for a child in a sub message that handles
+    /// failures according to the child's failure policy.
+    fn child_update_submsg(
+        &self,
+        deps: DepsMut,
+        child: &Addr,
+        msg: Binary,
+    ) -> Result<SubMsg, AuthorizationError<ErrorExt>> {
+        let policy = self.failure_policy(deps.as_ref(), child)?;
+        let execute = WasmMsg::Execute {
+            contract_addr: child.to_string(),
+            msg: msg.clone(),
+            funds: vec![],
+        };
+        Ok(match policy {
+            FailurePolicy::Strict {} => SubMsg::new(execute),
+            FailurePolicy::BestEffort {} => SubMsg {
+                id: self.get_update_reply_id(),
+                msg: execute.into(),
+                reply_on: self.reply_on(),
+                gas_limit: None,
+            },
+            FailurePolicy::RecordForRetry {} => {
+                let id = UpdateFailures::new().stage(deps.storage, child, msg)?;
+                SubMsg::reply_always(execute, id)
+            }
+        })
+    }
+
     /// If this authorization has children. We need to generate messages to
     /// update all of its children. This function generates those messages. It
     /// is the responsibility of update_authorization_state() to pass those in
     /// the response
+    #[allow(clippy::ptr_arg)]
     fn generate_child_update_msgs(
         &self,
-        deps: Deps,
-        msgs: &Vec<CosmosMsg>,
-        original_sender: &Addr,
+        mut deps: DepsMut,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
     ) -> Result<Vec<SubMsg>, AuthorizationError<ErrorExt>> {
-        let auths = self.get_sub_authorizations(deps)?;
-        let msgs: Result<Vec<_>, _> = auths
+        let auths = self.get_sub_authorizations(deps.as_ref())?;
+        auths
             .iter()
-            .map(|auth| -> Result<SubMsg, AuthorizationError<ErrorExt>> {
-                // All errors from submessages are ignored by default. If they matter, implementors should take care of checking them.
-                // In the future we may need a better way to handle updates
-                Ok(SubMsg{
-                    id: self.get_update_reply_id(),
-                    msg: wasm_execute(
-                        auth.to_string(),
-                        &msg::AuthoriazationExecuteMsg::<ExecuteExt>::UpdateExecutedAuthorizationState {
-                            msgs: msgs.clone(),
-                            sender: original_sender.clone(),
-                        },
-                        vec![],
-                    )?.into(),
-                    reply_on: self.reply_on(),
-                    gas_limit: None
-                })
-            })
-            .collect();
-
-        Ok(msgs?)
+            .map(|auth| self.child_update_msg(deps.branch(), auth, msgs, context))
+            .collect()
     }
 
     fn update_authorization_state(
         &self,
-        deps: Deps,
-        msgs: &Vec<CosmosMsg>,
-        sender: &Addr,
+        mut deps: DepsMut,
+        env: &Env,
+        msgs: &Vec<CosmosMsg<C>>,
+        context: &AuthorizationContext,
         original_sender: &Addr,
     ) -> Result<Response, AuthorizationError<ErrorExt>> {
-        let response = self.update_own_state(deps, msgs, sender, original_sender)?;
-        Ok(response.add_submessages(self.generate_child_update_msgs(
-            deps,
-            msgs,
-            original_sender,
-        )?))
+        let response = self.update_own_state(deps.branch(), env, msgs, context, original_sender)?;
+        Ok(response.add_submessages(self.generate_child_update_msgs(deps, msgs, context)?))
     }
 
     fn sub_message_reply(&self, msg: Reply) -> Result<Response, AuthorizationError<ErrorExt>> {
@@ -113,17 +331,96 @@ where
         Ok(Response::new().add_attribute("update_success", format!("{:?}", msg.result.unwrap())))
     }
 
+    /// Handles the replies of the child updates
+    fn reply(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        msg: Reply,
+    ) -> Result<Response, AuthorizationError<ErrorExt>> {
+        if msg.id == self.get_update_reply_id() {
+            return self.sub_message_reply(msg);
+        }
+        let id = msg.id;
+        UpdateFailures::new()
+            .handle_reply(deps.storage, msg)?
+            .ok_or_else(|| {
+                AuthorizationError::Std(StdError::generic_err(format!("Unknown reply id: {}", id)))
+            })
+    }
+
+    /// Only the owner can retry, as replaying an update can consume the
+    /// state of the child again
+    fn retry_failed_update(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        id: u64,
+    ) -> Result<Response, AuthorizationError<ErrorExt>> {
+        OwnershipState::new().assert_owner(deps.storage, &info.sender)?;
+        let failures = UpdateFailures::new();
+        let update = failures.failed.load(deps.storage, id)?;
+        failures.failed.remove(deps.storage, id);
+        let sub_msg = self.child_update_submsg(deps, &update.child, update.msg)?;
+        Ok(Response::new()
+            .add_attribute("action", "retry_failed_update")
+            .add_attribute("id", id.to_string())
+            .add_submessage(sub_msg))
+    }
+
+    fn query_failed_updates(
+        &self,
+        deps: Deps,
+        _env: Env,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Binary> {
+        let updates = UpdateFailures::new().list(deps.storage, start_after, limit)?;
+        to_binary(&FailedUpdatesResponse { updates })
+    }
+
+    fn update_ownership(
+        &self,
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        action: OwnershipAction,
+    ) -> Result<Response, AuthorizationError<ErrorExt>> {
+        let ownership =
+            OwnershipState::new().update(deps.api, deps.storage, &info.sender, action)?;
+        Ok(Response::new()
+            .add_attribute("action", "update_ownership")
+            .add_attribute(
+                "owner",
+                ownership
+                    .owner
+                    .map(String::from)
+                    .unwrap_or_else(|| "none".to_string()),
+            ))
+    }
+
+    fn query_ownership(&self, deps: Deps, _env: Env) -> StdResult<Binary> {
+        to_binary(&OwnershipState::new().ownership.load(deps.storage)?)
+    }
+
     // Entry Points
     fn execute(
         &self,
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        msg: msg::AuthoriazationExecuteMsg<ExecuteExt>,
+        msg: msg::AuthoriazationExecuteMsg<ExecuteExt, C>,
     ) -> Result<Response, AuthorizationError<ErrorExt>> {
         match msg {
-            msg::AuthoriazationExecuteMsg::UpdateExecutedAuthorizationState { msgs, sender } => {
-                self.update_authorization_state(deps.as_ref(), &msgs, &sender, &info.sender)
+            msg::AuthoriazationExecuteMsg::UpdateExecutedAuthorizationState { msgs, context } => {
+                self.update_authorization_state(deps, &env, &msgs, &context, &info.sender)
+            }
+            msg::AuthoriazationExecuteMsg::UpdateOwnership(action) => {
+                self.update_ownership(deps, env, info, action)
+            }
+            msg::AuthoriazationExecuteMsg::RetryFailedUpdate { id } => {
+                self.retry_failed_update(deps, env, info, id)
             }
             msg::AuthoriazationExecuteMsg::Extension(msg) => {
                 self.execute_extension(deps, env, info, msg)
@@ -145,12 +442,26 @@ where
         &self,
         deps: Deps,
         env: Env,
-        msg: msg::AuthoriazationQueryMsg<QueryExt>,
+        msg: msg::AuthoriazationQueryMsg<QueryExt, C>,
     ) -> StdResult<Binary> {
         match msg {
-            msg::AuthoriazationQueryMsg::IsAuthorized { msgs, sender } => {
-                self.query_authorizations(deps, msgs, sender)
+            msg::AuthoriazationQueryMsg::IsAuthorized { msgs, context } => {
+                self.query_authorizations(deps, env, msgs, context)
+            }
+            msg::AuthoriazationQueryMsg::IsAuthorizedBatch { requests } => {
+                self.query_authorizations_batch(deps, env, requests)
+            }
+            msg::AuthoriazationQueryMsg::IsAuthorizedPerMessage { msgs, context } => {
+                self.query_authorizations_per_message(deps, env, msgs, context)
+            }
+            msg::AuthoriazationQueryMsg::Explain { msgs, context } => {
+                self.query_explain(deps, env, msgs, context)
+            }
+            msg::AuthoriazationQueryMsg::FailedUpdates { start_after, limit } => {
+                self.query_failed_updates(deps, env, start_after, limit)
             }
+            msg::AuthoriazationQueryMsg::Ownership {} => self.query_ownership(deps, env),
+            msg::AuthoriazationQueryMsg::Info {} => self.query_info(deps, env),
             msg::AuthoriazationQueryMsg::Extension(msg) => self.query_extension(deps, env, msg),
         }
     }
@@ -1,6 +1,10 @@
 pub mod error;
 pub mod interface;
+pub mod migration;
 pub mod msg;
+pub mod ownership;
+pub mod updates;
+pub mod validation;
 
-pub use error::AuthorizationError;
-pub use interface::Authorization;
+pub use error::{AuthorizationError, ErrorCode};
+pub use interface::{Authorization, INTERFACE_VERSION};
@@ -0,0 +1,104 @@
+use cosmwasm_std::{StdError, Storage};
+pub use semver::Version;
+
+use crate::error::AuthorizationError;
+
+fn parse_version<E>(version: &str) -> Result<Version, AuthorizationError<E>> {
+    Version::parse(version)
+        .map_err(|e| AuthorizationError::Std(StdError::generic_err(e.to_string())))
+}
+
+/// Checks that the contract stored in `storage` can be migrated to version
+/// `new_version` of `contract_name`: it must be the same contract, and
+/// downgrades are not allowed. Returns the version being migrated from.
+pub fn assert_can_migrate<E>(
+    storage: &dyn Storage,
+    contract_name: &str,
+    new_version: &str,
+) -> Result<Version, AuthorizationError<E>> {
+    let stored = cw2::get_contract_version(storage)?;
+    if stored.contract != contract_name {
+        return Err(AuthorizationError::InvalidMigration {
+            reason: format!(
+                "Cannot migrate from {} to {}",
+                stored.contract, contract_name
+            ),
+        });
+    }
+
+    let from_version = parse_version(&stored.version)?;
+    if from_version > parse_version(new_version)? {
+        return Err(AuthorizationError::InvalidMigration {
+            reason: format!(
+                "Cannot downgrade from {} to {}",
+                stored.version, new_version
+            ),
+        });
+    }
+    Ok(from_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+    use crate::error::EmptyError;
+
+    const NAME: &str = "crates.io:message-filter";
+
+    fn storage_with(name: &str, version: &str) -> MockStorage {
+        let mut storage = MockStorage::new();
+        cw2::set_contract_version(&mut storage, name, version).unwrap();
+        storage
+    }
+
+    #[test]
+    fn upgrades() {
+        let storage = storage_with(NAME, "0.1.0");
+        let from = assert_can_migrate::<EmptyError>(&storage, NAME, "0.3.0").unwrap();
+        assert_eq!(from, Version::new(0, 1, 0));
+
+        // Migrating to the stored version is allowed
+        let storage = storage_with(NAME, "0.3.0");
+        let from = assert_can_migrate::<EmptyError>(&storage, NAME, "0.3.0").unwrap();
+        assert_eq!(from, Version::new(0, 3, 0));
+    }
+
+    #[test]
+    fn wrong_contract_name() {
+        let storage = storage_with("crates.io:whitelist", "0.1.0");
+        let err = assert_can_migrate::<EmptyError>(&storage, NAME, "0.3.0").unwrap_err();
+        assert_eq!(
+            err.reason(),
+            "Invalid migration: Cannot migrate from crates.io:whitelist to crates.io:message-filter"
+        );
+    }
+
+    #[test]
+    fn downgrade() {
+        let storage = storage_with(NAME, "0.3.1");
+        let err = assert_can_migrate::<EmptyError>(&storage, NAME, "0.3.0").unwrap_err();
+        assert_eq!(
+            err.reason(),
+            "Invalid migration: Cannot downgrade from 0.3.1 to 0.3.0"
+        );
+
+        // Pre-releases are older than the release
+        let storage = storage_with(NAME, "0.3.0");
+        assert_can_migrate::<EmptyError>(&storage, NAME, "0.3.0-beta").unwrap_err();
+    }
+
+    #[test]
+    fn missing_or_invalid_version() {
+        assert!(matches!(
+            assert_can_migrate::<EmptyError>(&MockStorage::new(), NAME, "0.3.0"),
+            Err(AuthorizationError::Std(_))
+        ));
+        let storage = storage_with(NAME, "latest");
+        assert!(matches!(
+            assert_can_migrate::<EmptyError>(&storage, NAME, "0.3.0"),
+            Err(AuthorizationError::Std(_))
+        ));
+    }
+}
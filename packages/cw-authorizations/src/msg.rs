@@ -1,20 +1,73 @@
-use cosmwasm_std::{Addr, CosmosMsg, CustomMsg, Empty};
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg, CustomMsg, Empty};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::error::{AuthorizationError, ErrorCode, UNAUTHORIZED_CODE};
+use crate::ownership::OwnershipAction;
+
+/// Everything an authorization may need to know about who is trying to
+/// execute a set of messages and how.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuthorizationContext {
+    /// The address that wants to execute the messages
+    pub sender: Addr,
+    /// The funds the sender attached to the execution
+    #[serde(default)]
+    pub funds: Vec<Coin>,
+    /// An optional free-form note attached by the sender
+    pub memo: Option<String>,
+    /// The proposal the messages belong to, if they come from a proposal
+    pub proposal_id: Option<u64>,
+}
+
+impl AuthorizationContext {
+    pub fn new(sender: Addr) -> Self {
+        AuthorizationContext {
+            sender,
+            funds: vec![],
+            memo: None,
+            proposal_id: None,
+        }
+    }
+}
+
+impl From<Addr> for AuthorizationContext {
+    fn from(sender: Addr) -> Self {
+        AuthorizationContext::new(sender)
+    }
+}
+
+/// A single IsAuthorized check, used to batch several of them in one query.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AuthorizationRequest<C = Empty> {
+    pub msgs: Vec<CosmosMsg<C>>,
+    pub context: AuthorizationContext,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum AuthoriazationExecuteMsg<ExecuteExt = Empty>
+pub enum AuthoriazationExecuteMsg<ExecuteExt = Empty, C = Empty>
 where
     ExecuteExt: CustomMsg,
+    C: CustomMsg,
 {
     /// Some authorizations may want to track information about the users or
     /// messages to determine if they authorize or not. This message should be
     /// sent every time the authorizations are successfully used so that
     /// sub-authorizations can update their internal state.
     UpdateExecutedAuthorizationState {
-        msgs: Vec<CosmosMsg>,
-        sender: Addr,
+        msgs: Vec<CosmosMsg<C>>,
+        context: AuthorizationContext,
+    },
+
+    /// Proposes, accepts or renounces the ownership of the contract
+    UpdateOwnership(OwnershipAction),
+
+    /// Sends a child update that failed under the RecordForRetry policy
+    /// again. The update is removed from the failed updates if it succeeds.
+    /// Only the owner can trigger a retry.
+    RetryFailedUpdate {
+        id: u64,
     },
 
     // Extensions allow implementors to add their own custom messages to the contract
@@ -23,11 +76,48 @@ where
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum AuthoriazationQueryMsg<QueryExt = Empty>
+pub enum AuthoriazationQueryMsg<QueryExt = Empty, C = Empty>
 where
     QueryExt: CustomMsg,
+    C: CustomMsg,
 {
-    IsAuthorized { msgs: Vec<CosmosMsg>, sender: Addr },
+    IsAuthorized {
+        msgs: Vec<CosmosMsg<C>>,
+        context: AuthorizationContext,
+    },
+
+    /// Checks several requests at once. The responses are returned in the
+    /// same order as the requests.
+    IsAuthorizedBatch {
+        requests: Vec<AuthorizationRequest<C>>,
+    },
+
+    /// Returns one verdict per message, in the same order as the messages, so
+    /// that callers can tell which messages caused a rejection.
+    IsAuthorizedPerMessage {
+        msgs: Vec<CosmosMsg<C>>,
+        context: AuthorizationContext,
+    },
+
+    /// Returns the decision tree for an IsAuthorized query: the verdict of
+    /// this authorization and, recursively, of all its sub-authorizations.
+    Explain {
+        msgs: Vec<CosmosMsg<C>>,
+        context: AuthorizationContext,
+    },
+
+    /// Lists the child updates that failed under the RecordForRetry policy
+    FailedUpdates {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the current and pending owners of the contract
+    Ownership {},
+
+    /// Describes this authorization contract so that tooling can discover
+    /// what an arbitrary address is before using it.
+    Info {},
 
     // Extensions allow implementors to add their own custom messages to the contract
     Extension(QueryExt),
@@ -36,4 +126,110 @@ where
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct IsAuthorizedResponse {
     pub authorized: bool,
+    /// A machine-readable code explaining why the request was not
+    /// authorized. Always None when authorized is true.
+    pub code: Option<String>,
+    /// A human-readable explanation of why the request was not authorized.
+    pub reason: Option<String>,
+}
+
+impl IsAuthorizedResponse {
+    pub fn authorized() -> Self {
+        IsAuthorizedResponse {
+            authorized: true,
+            code: None,
+            reason: None,
+        }
+    }
+
+    pub fn unauthorized(code: impl Into<String>, reason: Option<String>) -> Self {
+        IsAuthorizedResponse {
+            authorized: false,
+            code: Some(code.into()),
+            reason,
+        }
+    }
+
+    /// Builds the response for the result of `Authorization::is_authorized`.
+    /// Errors are reported as unauthorized with the error's code and reason.
+    pub fn from_result<ErrorExt: ErrorCode>(
+        result: Result<bool, AuthorizationError<ErrorExt>>,
+    ) -> Self {
+        match result {
+            Ok(true) => IsAuthorizedResponse::authorized(),
+            Ok(false) => IsAuthorizedResponse::unauthorized(UNAUTHORIZED_CODE, None),
+            Err(e) => IsAuthorizedResponse::unauthorized(e.code(), Some(e.reason())),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsAuthorizedBatchResponse {
+    pub responses: Vec<IsAuthorizedResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsAuthorizedPerMessageResponse {
+    pub responses: Vec<IsAuthorizedResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DecisionNode {
+    /// The address of the authorization contract that made this decision
+    pub contract: Addr,
+    /// The type of authorization (i.e.: message_filter, satisfies_all, ...)
+    pub contract_type: String,
+    pub verdict: IsAuthorizedResponse,
+    /// The decisions of the sub-authorizations of this contract, if any
+    pub children: Vec<DecisionNode>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InfoResponse {
+    /// The type of authorization (i.e.: message_filter, satisfies_all, ...)
+    pub contract_type: String,
+    /// The cw2 contract name
+    pub contract_name: String,
+    /// The cw2 contract version
+    pub contract_version: String,
+    /// The version of the cw-authorizations interface this contract implements
+    pub interface_version: String,
+    /// The sub-authorizations of this contract, if any
+    pub children: Vec<Addr>,
+    /// Whether the contract keeps track of executed messages in
+    /// UpdateExecutedAuthorizationState
+    pub stateful: bool,
+}
+
+/// What to do when updating the state of a sub-authorization fails
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// The whole execution is reverted
+    Strict {},
+    /// The error is reported in the response attributes and ignored
+    BestEffort {},
+    /// The update is stored so that it can be retried with RetryFailedUpdate
+    RecordForRetry {},
+}
+
+impl Default for FailurePolicy {
+    fn default() -> Self {
+        FailurePolicy::BestEffort {}
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FailedUpdate {
+    pub id: u64,
+    /// The sub-authorization whose update failed
+    pub child: Addr,
+    /// The UpdateExecutedAuthorizationState message that was sent to the child
+    pub msg: Binary,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FailedUpdatesResponse {
+    pub updates: Vec<FailedUpdate>,
 }
@@ -0,0 +1,292 @@
+use cosmwasm_std::{Addr, Api, Storage};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AuthorizationError;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Ownership {
+    /// The address allowed to administer the contract. None if ownership has
+    /// been renounced
+    pub owner: Option<Addr>,
+    /// The address that has been proposed as the new owner, if any. It only
+    /// becomes the owner once it accepts
+    pub pending_owner: Option<Addr>,
+}
+
+/// Ownership is transferred in two steps: the owner proposes a new owner, and
+/// the new owner accepts. This prevents transferring ownership to an address
+/// that can't use it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OwnershipAction {
+    /// Proposes a new owner. Replaces any previous proposal
+    ProposeOwner { new_owner: String },
+    /// Accepts a proposal. Must be sent by the proposed owner
+    AcceptOwnership {},
+    /// Leaves the contract without an owner. This can't be undone
+    RenounceOwnership {},
+}
+
+/// The owner of an authorization contract. All the reference contracts use
+/// this so that ownership is stored and transferred the same way everywhere.
+pub struct OwnershipState {
+    pub ownership: Item<'static, Ownership>,
+}
+
+impl OwnershipState {
+    pub const fn new() -> Self {
+        OwnershipState {
+            ownership: Item::new("ownership"),
+        }
+    }
+
+    pub fn initialize<E>(
+        &self,
+        storage: &mut dyn Storage,
+        owner: Addr,
+    ) -> Result<Ownership, AuthorizationError<E>> {
+        let ownership = Ownership {
+            owner: Some(owner),
+            pending_owner: None,
+        };
+        self.ownership.save(storage, &ownership)?;
+        Ok(ownership)
+    }
+
+    pub fn assert_owner<E>(
+        &self,
+        storage: &dyn Storage,
+        sender: &Addr,
+    ) -> Result<(), AuthorizationError<E>> {
+        match self.ownership.load(storage)?.owner {
+            Some(owner) if &owner == sender => Ok(()),
+            Some(_) => Err(AuthorizationError::Unauthorized {
+                reason: Some(format!("{} is not the owner of this contract", sender)),
+            }),
+            None => Err(AuthorizationError::Unauthorized {
+                reason: Some("Ownership of this contract has been renounced".to_string()),
+            }),
+        }
+    }
+
+    pub fn update<E>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        sender: &Addr,
+        action: OwnershipAction,
+    ) -> Result<Ownership, AuthorizationError<E>> {
+        let mut ownership = self.ownership.load(storage)?;
+        match action {
+            OwnershipAction::ProposeOwner { new_owner } => {
+                self.assert_owner(storage, sender)?;
+                ownership.pending_owner = Some(api.addr_validate(&new_owner)?);
+            }
+            OwnershipAction::AcceptOwnership {} => {
+                if ownership.pending_owner.as_ref() != Some(sender) {
+                    return Err(AuthorizationError::Unauthorized {
+                        reason: Some(format!("{} is not the pending owner", sender)),
+                    });
+                }
+                ownership.owner = ownership.pending_owner.take();
+            }
+            OwnershipAction::RenounceOwnership {} => {
+                self.assert_owner(storage, sender)?;
+                ownership.owner = None;
+                ownership.pending_owner = None;
+            }
+        }
+        self.ownership.save(storage, &ownership)?;
+        Ok(ownership)
+    }
+}
+
+impl Default for OwnershipState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{MockApi, MockStorage};
+
+    use super::*;
+    use crate::error::EmptyError;
+
+    const OWNER: &str = "owner";
+    const NEW_OWNER: &str = "new_owner";
+
+    fn setup() -> (MockApi, MockStorage, OwnershipState) {
+        let api = MockApi::default();
+        let mut storage = MockStorage::new();
+        let state = OwnershipState::new();
+        state
+            .initialize::<EmptyError>(&mut storage, Addr::unchecked(OWNER))
+            .unwrap();
+        (api, storage, state)
+    }
+
+    fn propose(new_owner: &str) -> OwnershipAction {
+        OwnershipAction::ProposeOwner {
+            new_owner: new_owner.to_string(),
+        }
+    }
+
+    #[test]
+    fn propose_and_accept() {
+        let (api, mut storage, state) = setup();
+        let owner = Addr::unchecked(OWNER);
+        let new_owner = Addr::unchecked(NEW_OWNER);
+
+        // Only the owner can propose
+        let err = state
+            .update::<EmptyError>(&api, &mut storage, &new_owner, propose(NEW_OWNER))
+            .unwrap_err();
+        assert!(matches!(err, AuthorizationError::Unauthorized { .. }));
+
+        // Proposing doesn't transfer the ownership, and replaces any previous
+        // proposal
+        state
+            .update::<EmptyError>(&api, &mut storage, &owner, propose("someone"))
+            .unwrap();
+        let ownership = state
+            .update::<EmptyError>(&api, &mut storage, &owner, propose(NEW_OWNER))
+            .unwrap();
+        assert_eq!(
+            ownership,
+            Ownership {
+                owner: Some(owner.clone()),
+                pending_owner: Some(new_owner.clone()),
+            }
+        );
+        state.assert_owner::<EmptyError>(&storage, &owner).unwrap();
+
+        let ownership = state
+            .update::<EmptyError>(
+                &api,
+                &mut storage,
+                &new_owner,
+                OwnershipAction::AcceptOwnership {},
+            )
+            .unwrap();
+        assert_eq!(
+            ownership,
+            Ownership {
+                owner: Some(new_owner.clone()),
+                pending_owner: None,
+            }
+        );
+        assert_eq!(state.ownership.load(&storage).unwrap(), ownership);
+        state
+            .assert_owner::<EmptyError>(&storage, &new_owner)
+            .unwrap();
+        state
+            .assert_owner::<EmptyError>(&storage, &owner)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn accept_by_wrong_account() {
+        let (api, mut storage, state) = setup();
+        let owner = Addr::unchecked(OWNER);
+
+        // There is nothing to accept without a proposal
+        state
+            .update::<EmptyError>(
+                &api,
+                &mut storage,
+                &Addr::unchecked(NEW_OWNER),
+                OwnershipAction::AcceptOwnership {},
+            )
+            .unwrap_err();
+
+        state
+            .update::<EmptyError>(&api, &mut storage, &owner, propose(NEW_OWNER))
+            .unwrap();
+        for sender in [&owner, &Addr::unchecked("someone")] {
+            let err = state
+                .update::<EmptyError>(
+                    &api,
+                    &mut storage,
+                    sender,
+                    OwnershipAction::AcceptOwnership {},
+                )
+                .unwrap_err();
+            assert_eq!(err.reason(), format!("{} is not the pending owner", sender));
+        }
+        assert_eq!(
+            state.ownership.load(&storage).unwrap().pending_owner,
+            Some(Addr::unchecked(NEW_OWNER))
+        );
+    }
+
+    #[test]
+    fn accept_after_renounce() {
+        let (api, mut storage, state) = setup();
+        let owner = Addr::unchecked(OWNER);
+        let new_owner = Addr::unchecked(NEW_OWNER);
+
+        state
+            .update::<EmptyError>(&api, &mut storage, &owner, propose(NEW_OWNER))
+            .unwrap();
+        let ownership = state
+            .update::<EmptyError>(
+                &api,
+                &mut storage,
+                &owner,
+                OwnershipAction::RenounceOwnership {},
+            )
+            .unwrap();
+        assert_eq!(
+            ownership,
+            Ownership {
+                owner: None,
+                pending_owner: None,
+            }
+        );
+
+        // The proposal made before renouncing can't be accepted
+        state
+            .update::<EmptyError>(
+                &api,
+                &mut storage,
+                &new_owner,
+                OwnershipAction::AcceptOwnership {},
+            )
+            .unwrap_err();
+        assert_eq!(state.ownership.load(&storage).unwrap(), ownership);
+    }
+
+    #[test]
+    fn no_proposals_after_renounce() {
+        let (api, mut storage, state) = setup();
+        let owner = Addr::unchecked(OWNER);
+
+        state
+            .update::<EmptyError>(
+                &api,
+                &mut storage,
+                &owner,
+                OwnershipAction::RenounceOwnership {},
+            )
+            .unwrap();
+
+        // Nobody can make a new proposal, so the contract stays ownerless
+        for sender in [&owner, &Addr::unchecked(NEW_OWNER)] {
+            let err = state
+                .update::<EmptyError>(&api, &mut storage, sender, propose(NEW_OWNER))
+                .unwrap_err();
+            assert_eq!(
+                err.reason(),
+                "Ownership of this contract has been renounced"
+            );
+            state
+                .assert_owner::<EmptyError>(&storage, sender)
+                .unwrap_err();
+        }
+        assert_eq!(state.ownership.load(&storage).unwrap().pending_owner, None);
+    }
+}
@@ -0,0 +1,229 @@
+use cosmwasm_std::{Addr, Binary, Order, Reply, Response, StdResult, Storage};
+use cw_storage_plus::{Bound, Item, Map};
+
+use crate::interface::UPDATE_REPLY_ID;
+use crate::msg::FailedUpdate;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// Keeps track of the child updates sent under the RecordForRetry policy.
+/// Updates are staged before being sent, and moved to the failed updates if
+/// their reply is an error. The staging id is used as the reply id.
+pub struct UpdateFailures {
+    pub next_id: Item<'static, u64>,
+    pub pending: Map<'static, u64, (Addr, Binary)>,
+    pub failed: Map<'static, u64, FailedUpdate>,
+}
+
+impl UpdateFailures {
+    pub const fn new() -> Self {
+        UpdateFailures {
+            next_id: Item::new("update_failures_next_id"),
+            pending: Map::new("update_failures_pending"),
+            failed: Map::new("update_failures_failed"),
+        }
+    }
+
+    /// Stores an update before it is sent and returns the id to use as the
+    /// reply id of its sub message.
+    pub fn stage(&self, storage: &mut dyn Storage, child: &Addr, msg: Binary) -> StdResult<u64> {
+        // Ids start after the update reply id so that they never collide
+        // with it.
+        let id = self
+            .next_id
+            .may_load(storage)?
+            .unwrap_or(UPDATE_REPLY_ID + 1);
+        self.next_id.save(storage, &(id + 1))?;
+        self.pending.save(storage, id, &(child.clone(), msg))?;
+        Ok(id)
+    }
+
+    /// Handles the reply of a staged update. Failures are recorded and
+    /// successes are forgotten. Returns None if the reply doesn't belong to a
+    /// staged update.
+    pub fn handle_reply(
+        &self,
+        storage: &mut dyn Storage,
+        reply: Reply,
+    ) -> StdResult<Option<Response>> {
+        let (child, msg) = match self.pending.may_load(storage, reply.id)? {
+            Some(pending) => pending,
+            None => return Ok(None),
+        };
+        self.pending.remove(storage, reply.id);
+
+        let response = match reply.result.into_result() {
+            Ok(_) => Response::new().add_attribute("update_success", child),
+            Err(error) => {
+                self.failed.save(
+                    storage,
+                    reply.id,
+                    &FailedUpdate {
+                        id: reply.id,
+                        child,
+                        msg,
+                        error: error.clone(),
+                    },
+                )?;
+                Response::new()
+                    .add_attribute("update_error", error)
+                    .add_attribute("failed_update_id", reply.id.to_string())
+            }
+        };
+        Ok(Some(response))
+    }
+
+    pub fn list(
+        &self,
+        storage: &dyn Storage,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<FailedUpdate>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        self.failed
+            .range(
+                storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, update)| update))
+            .collect()
+    }
+}
+
+impl Default for UpdateFailures {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{to_binary, SubMsgResponse, SubMsgResult};
+
+    use super::*;
+
+    fn reply(id: u64, result: Result<(), &str>) -> Reply {
+        Reply {
+            id,
+            result: match result {
+                Ok(()) => SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+                Err(error) => SubMsgResult::Err(error.to_string()),
+            },
+        }
+    }
+
+    #[test]
+    fn stage() {
+        let mut storage = MockStorage::new();
+        let failures = UpdateFailures::new();
+        let child = Addr::unchecked("child");
+
+        let first = failures
+            .stage(&mut storage, &child, to_binary("first").unwrap())
+            .unwrap();
+        let second = failures
+            .stage(&mut storage, &child, to_binary("second").unwrap())
+            .unwrap();
+        assert_eq!(first, UPDATE_REPLY_ID + 1);
+        assert_eq!(second, first + 1);
+        assert_eq!(
+            failures.pending.load(&storage, second).unwrap(),
+            (child, to_binary("second").unwrap())
+        );
+        assert!(failures.list(&storage, None, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn handle_reply() {
+        let mut storage = MockStorage::new();
+        let failures = UpdateFailures::new();
+        let child = Addr::unchecked("child");
+        let succeeded = failures
+            .stage(&mut storage, &child, to_binary("succeeded").unwrap())
+            .unwrap();
+        let failed = failures
+            .stage(&mut storage, &child, to_binary("failed").unwrap())
+            .unwrap();
+
+        // Replies that don't belong to a staged update are left to the caller
+        assert_eq!(
+            failures
+                .handle_reply(&mut storage, reply(UPDATE_REPLY_ID, Ok(())))
+                .unwrap(),
+            None
+        );
+
+        // Successes are forgotten
+        let response = failures
+            .handle_reply(&mut storage, reply(succeeded, Ok(())))
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.attributes[0].key, "update_success");
+        assert!(!failures.pending.has(&storage, succeeded));
+        assert!(!failures.failed.has(&storage, succeeded));
+
+        // Failures are recorded under the staging id
+        let response = failures
+            .handle_reply(&mut storage, reply(failed, Err("out of uses")))
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.attributes[0].value, "out of uses");
+        assert!(!failures.pending.has(&storage, failed));
+        assert_eq!(
+            failures.list(&storage, None, None).unwrap(),
+            vec![FailedUpdate {
+                id: failed,
+                child,
+                msg: to_binary("failed").unwrap(),
+                error: "out of uses".to_string(),
+            }]
+        );
+
+        // A reply is only handled once
+        assert_eq!(
+            failures
+                .handle_reply(&mut storage, reply(failed, Err("out of uses")))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn list() {
+        let mut storage = MockStorage::new();
+        let failures = UpdateFailures::new();
+        let child = Addr::unchecked("child");
+        let ids: Vec<u64> = (0..(MAX_LIMIT + 5))
+            .map(|_| {
+                let id = failures
+                    .stage(&mut storage, &child, Binary::default())
+                    .unwrap();
+                failures
+                    .handle_reply(&mut storage, reply(id, Err("failed")))
+                    .unwrap();
+                id
+            })
+            .collect();
+
+        let listed = |start_after, limit| -> Vec<u64> {
+            failures
+                .list(&storage, start_after, limit)
+                .unwrap()
+                .into_iter()
+                .map(|update| update.id)
+                .collect()
+        };
+        assert_eq!(listed(None, None), ids[..DEFAULT_LIMIT as usize]);
+        assert_eq!(listed(Some(ids[1]), Some(2)), ids[2..4]);
+        assert_eq!(listed(None, Some(MAX_LIMIT + 1)).len(), MAX_LIMIT as usize);
+        assert!(listed(ids.last().copied(), None).is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use cosmwasm_std::{Addr, Deps, Empty};
+
+use crate::error::AuthorizationError;
+use crate::interface::INTERFACE_VERSION;
+use crate::msg::{AuthoriazationQueryMsg, InfoResponse};
+
+/// The maximum depth of the subtree under a composite, counting the composite
+/// itself, used by composites that don't configure one.
+pub const DEFAULT_MAX_DEPTH: u32 = 10;
+
+/// Returns the (major, minor) pair of a `major.minor.patch` version string.
+fn major_minor(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Two interface versions are compatible if they share the major version. For
+/// pre-1.0 versions the minor version must match as well.
+pub fn is_compatible_interface(ours: &str, theirs: &str) -> bool {
+    match (major_minor(ours), major_minor(theirs)) {
+        (Some((0, our_minor)), Some((0, their_minor))) => our_minor == their_minor,
+        (Some((our_major, _)), Some((their_major, _))) => our_major == their_major,
+        _ => false,
+    }
+}
+
+/// Validates that `addr` is an authorization contract that speaks a compatible
+/// version of the interface. Composites should call this before registering a
+/// child so that a wrong address doesn't silently turn into a permanent
+/// rejection.
+pub fn validate_child<ErrorExt>(
+    deps: Deps,
+    addr: &Addr,
+) -> Result<Addr, AuthorizationError<ErrorExt>> {
+    let addr = deps.api.addr_validate(addr.as_str())?;
+    let info = query_info(deps, &addr)?;
+
+    if !is_compatible_interface(INTERFACE_VERSION, &info.interface_version) {
+        return Err(AuthorizationError::IncompatibleChild {
+            addr: addr.to_string(),
+            reason: format!(
+                "interface version {} is not compatible with {}",
+                info.interface_version, INTERFACE_VERSION
+            ),
+        });
+    }
+    Ok(addr)
+}
+
+fn query_info<ErrorExt>(
+    deps: Deps,
+    addr: &Addr,
+) -> Result<InfoResponse, AuthorizationError<ErrorExt>> {
+    deps.querier
+        .query_wasm_smart(addr.clone(), &AuthoriazationQueryMsg::<Empty>::Info {})
+        .map_err(|e| AuthorizationError::IncompatibleChild {
+            addr: addr.to_string(),
+            reason: format!("Info query failed: {}", e),
+        })
+}
+
+/// Returns the depth of the tree rooted at `addr`, failing if `ancestor` is
+/// found in it or if it has more than `max_depth` levels.
+fn subtree_depth<ErrorExt>(
+    deps: Deps,
+    addr: &Addr,
+    ancestor: Option<&Addr>,
+    max_depth: u32,
+) -> Result<u32, AuthorizationError<ErrorExt>> {
+    if Some(addr) == ancestor {
+        return Err(AuthorizationError::CycleDetected {
+            addr: addr.to_string(),
+        });
+    }
+    if max_depth == 0 {
+        return Err(AuthorizationError::MaxDepthExceeded { max_depth });
+    }
+
+    let mut depth = 0;
+    for child in query_info(deps, addr)?.children {
+        let child_depth = subtree_depth(deps, &child, ancestor, max_depth - 1)?;
+        depth = depth.max(child_depth);
+    }
+    Ok(depth + 1)
+}
+
+/// Checks that `child` can be added under `parent` without creating a cycle
+/// and without the subtree rooted at `parent` exceeding `max_depth` levels. The
+/// parent can be omitted when it can't be part of the child's tree yet (i.e.:
+/// while it is being instantiated), in which case only the depth is checked.
+///
+/// Composites don't know their ancestors, so the levels above `parent` aren't
+/// counted: a tree whose composites all respect their own limit can still be
+/// deeper than any of them. Cycles are caught regardless, so queries on the
+/// tree always terminate.
+pub fn validate_edge<ErrorExt>(
+    deps: Deps,
+    parent: Option<&Addr>,
+    child: &Addr,
+    max_depth: u32,
+) -> Result<(), AuthorizationError<ErrorExt>> {
+    // The parent takes up one level of the tree. The depth error is reported
+    // against the full tree rather than the level it was detected at.
+    let remaining = max_depth.saturating_sub(1);
+    subtree_depth(deps, child, parent, remaining).map_err(|e| match e {
+        AuthorizationError::MaxDepthExceeded { .. } => {
+            AuthorizationError::MaxDepthExceeded { max_depth }
+        }
+        e => e,
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interface_compatibility() {
+        assert!(is_compatible_interface("0.1.0", "0.1.3"));
+        assert!(!is_compatible_interface("0.1.0", "0.2.0"));
+        assert!(is_compatible_interface("1.0.0", "1.4.2"));
+        assert!(!is_compatible_interface("1.0.0", "2.0.0"));
+        assert!(!is_compatible_interface("0.1.0", "not a version"));
+    }
+}